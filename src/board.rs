@@ -68,6 +68,65 @@ impl std::ops::Not for Player {
     }
 }
 
+/// Reason a move cannot be played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    ColumnFull(Column),
+    GameOver,
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::ColumnFull(c) => write!(f, "column {:?} is full", c),
+            MoveError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+const FULL: u64 = (1 << (Board::ROWS * Board::COLS)) - 1;
+const FIRST_COLUMN: u64 = column_mask(0);
+const LAST_COLUMN: u64 = column_mask(Board::COLS - 1);
+// rows 1, 3 and 5 counting from 1 at the bottom
+const ODD_ROWS: u64 = Board::BOTTOM_ROW
+    | Board::BOTTOM_ROW << (2 * Board::COLS)
+    | Board::BOTTOM_ROW << (4 * Board::COLS);
+
+// (shift, guard when shifting left, guard when shifting right) for the
+// horizontal, vertical and both diagonal directions
+const DIRECTIONS: [(usize, u64, u64); 4] = [
+    (1, !FIRST_COLUMN, !LAST_COLUMN),
+    (Board::COLS, FULL, FULL),
+    (Board::COLS + 1, !FIRST_COLUMN, !LAST_COLUMN),
+    (Board::COLS - 1, !LAST_COLUMN, !FIRST_COLUMN),
+];
+
+pub(crate) const fn column_mask(c: usize) -> u64 {
+    let mut mask = 0;
+    let mut r = 0;
+    while r < Board::ROWS {
+        mask |= 1 << (r * Board::COLS + c);
+        r += 1;
+    }
+    mask
+}
+
+/// Cells not yet in `mask` that would complete four in a row for `stones`
+pub(crate) fn threat_cells(stones: u64, mask: u64) -> u64 {
+    let mut cells = 0;
+    for (shift, left, right) in DIRECTIONS {
+        let up = |x: u64| (x << shift) & left & FULL;
+        let down = |x: u64| (x >> shift) & right;
+        let (u1, d1) = (up(stones), down(stones));
+        let (u2, d2) = (up(u1) & u1, down(d1) & d1);
+        let (u3, d3) = (up(up(u1)) & u2, down(down(d1)) & d2);
+        cells |= u3 | (u2 & d1) | (u1 & d2) | d3;
+    }
+    cells & !mask & FULL
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Board {
     pub red: u64,
@@ -80,6 +139,7 @@ impl Board {
     const EMPTY_CELL: Cell = Cell::Empty;
     const RED_CELL: Cell = Cell::Occupied(Player::Red);
     const YELLOW_CELL: Cell = Cell::Occupied(Player::Yellow);
+    const BOTTOM_ROW: u64 = (1 << Board::COLS) - 1;
 
     pub fn is_possible(&self, c: &Column) -> bool {
         self[(Board::ROWS - 1, *c)] == Self::EMPTY_CELL
@@ -93,7 +153,73 @@ impl Board {
             .collect()
     }
 
+    /// Compact 49 bit key unique to this board: the red stones plus a marker on
+    /// the lowest empty cell of every column (on a seventh row for full columns)
+    pub fn key(&self) -> u64 {
+        let mask = self.red | self.yellow;
+        self.red | (((mask << Board::COLS) | Self::BOTTOM_ROW) & !mask)
+    }
+
+    /// Mask of the discs of player `p`
+    pub fn stones(&self, p: Player) -> u64 {
+        match p {
+            Player::Red => self.red,
+            Player::Yellow => self.yellow,
+        }
+    }
+
+    /// Mask of the cells a disc would land on in every column that is not full
+    pub fn playable(&self) -> u64 {
+        let mask = self.red | self.yellow;
+        ((mask << Board::COLS) | Self::BOTTOM_ROW) & !mask & FULL
+    }
+
+    /// Empty cells on which player `p` would complete four in a row, whether
+    /// or not they can be played yet
+    pub fn threats(&self, p: Player) -> u64 {
+        threat_cells(self.stones(p), self.red | self.yellow)
+    }
+
+    /// Threats of player `p` on odd rows, counting from 1 at the bottom
+    pub fn odd_threats(&self, p: Player) -> u64 {
+        self.threats(p) & ODD_ROWS
+    }
+
+    /// Threats of player `p` on even rows, counting from 1 at the bottom
+    pub fn even_threats(&self, p: Player) -> u64 {
+        self.threats(p) & !ODD_ROWS
+    }
+
+    /// Columns in which player `p` wins immediately
+    pub fn winning_moves(&self, p: Player) -> Vec<Column> {
+        Self::columns(self.threats(p) & self.playable())
+    }
+
+    /// Columns player `p` has to play to stop the opponent from winning on
+    /// their next move. More than one means the opponent cannot be stopped.
+    pub fn forced_moves(&self, p: Player) -> Vec<Column> {
+        self.winning_moves(!p)
+    }
+
+    /// Columns holding any of the cells in `cells`
+    pub fn columns(cells: u64) -> Vec<Column> {
+        Column::ALL
+            .iter()
+            .filter(|&&c| cells & column_mask(c as usize) != 0)
+            .copied()
+            .collect()
+    }
+
     pub fn apply_move(&self, c: Column, p: Player) -> (Self, usize) {
+        match self.try_apply_move(c, p) {
+            Ok(result) => result,
+            Err(e) => panic!("Invalid move: {}", e),
+        }
+    }
+
+    /// Drop a disc of player `p` in column `c`, returning the new board and the
+    /// row the disc landed in
+    pub fn try_apply_move(&self, c: Column, p: Player) -> Result<(Self, usize), MoveError> {
         let mut board = *self;
         for r in 0..Board::ROWS {
             let mask = 1 << (r * Board::COLS + c as usize);
@@ -102,10 +228,10 @@ impl Board {
                     Player::Red => board.red |= mask,
                     Player::Yellow => board.yellow |= mask,
                 }
-                return (board, r);
+                return Ok((board, r));
             }
         }
-        panic!("Invalid move");
+        Err(MoveError::ColumnFull(c))
     }
 }
 
@@ -129,3 +255,111 @@ impl std::ops::Index<(usize, Column)> for Board {
         self.index((r, c as usize))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::CONNECT_FOURS;
+    use crate::state::GameState;
+    use rand::rngs::SmallRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    fn cell(r: usize, c: usize) -> u64 {
+        1 << (r * Board::COLS + c)
+    }
+
+    /// Empty cells completing a line of `CONNECT_FOURS` with `stones`
+    fn naive_threats(stones: u64, mask: u64) -> u64 {
+        let empty = (0..Board::ROWS * Board::COLS)
+            .map(|i| 1 << i)
+            .filter(|&x| mask & x == 0);
+        empty
+            .filter(|&x| {
+                CONNECT_FOURS
+                    .iter()
+                    .any(|&line| line & x != 0 && line & !x & !stones == 0)
+            })
+            .fold(0, |cells, x| cells | x)
+    }
+
+    fn naive_playable(board: &Board) -> u64 {
+        let mask = board.red | board.yellow;
+        (0..Board::COLS)
+            .filter_map(|c| {
+                (0..Board::ROWS)
+                    .map(|r| cell(r, c))
+                    .find(|&x| mask & x == 0)
+            })
+            .fold(0, |cells, x| cells | x)
+    }
+
+    fn naive_winning_moves(board: &Board, p: Player) -> Vec<Column> {
+        let wins = |c: &Column| {
+            // only lines through the new disc, the board may be won already
+            let (after, r) = board.apply_move(*c, p);
+            let disc = cell(r, *c as usize);
+            CONNECT_FOURS
+                .iter()
+                .any(|&line| line & disc != 0 && after.stones(p) & line == line)
+        };
+        board.possible_moves().into_iter().filter(wins).collect()
+    }
+
+    /// Positions of random games played to the end
+    fn random_positions(games: usize) -> Vec<Board> {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut boards = vec![];
+        for _ in 0..games {
+            let mut state = GameState::default();
+            boards.push(state.board);
+            while !state.state.is_over() {
+                let moves = state.possible_moves();
+                state.apply_move(moves[rng.random_range(0..moves.len())]);
+                boards.push(state.board);
+            }
+        }
+        boards
+    }
+
+    #[test]
+    fn threat_cells_match_the_lines_on_random_cells() {
+        // stones along the board edges catch shifts wrapping into the next row
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..2000 {
+            let stones = rng.random::<u64>() & rng.random::<u64>() & FULL;
+            let mask = stones | (rng.random::<u64>() & rng.random::<u64>() & FULL);
+            assert_eq!(
+                threat_cells(stones, mask),
+                naive_threats(stones, mask),
+                "{:#x} {:#x}",
+                stones,
+                mask
+            );
+        }
+    }
+
+    #[test]
+    fn bitboard_queries_match_brute_force_on_random_games() {
+        let mut keys = HashMap::new();
+        for board in random_positions(200) {
+            let mask = board.red | board.yellow;
+            assert_eq!(board.playable(), naive_playable(&board));
+            let key = board.key();
+            assert_ne!(key, 0);
+            assert!(*keys.entry(key).or_insert(board) == board, "{:#x}", key);
+            for p in [Player::Red, Player::Yellow] {
+                let threats = naive_threats(board.stones(p), mask);
+                assert_eq!(board.threats(p), threats);
+                let odd_rows = (0..Board::ROWS)
+                    .step_by(2)
+                    .fold(0, |rows, r| rows | Board::BOTTOM_ROW << (r * Board::COLS));
+                assert_eq!(board.odd_threats(p), threats & odd_rows);
+                assert_eq!(board.even_threats(p), threats & !odd_rows);
+                assert_eq!(board.winning_moves(p), naive_winning_moves(&board, p));
+                assert_eq!(board.forced_moves(p), naive_winning_moves(&board, !p));
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::board::Board;
 use crate::board::Cell;
 use crate::board::Player;
+use crate::state::GameState;
 use ansi_term::Color;
 use ansi_term::Style;
 use itertools;
@@ -26,8 +27,12 @@ impl BoardAnsiWriter {
     };
 }
 
-impl std::fmt::Display for BoardAnsiWriter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl BoardAnsiWriter {
+    // background behind the discs of a winning line
+    const HIGHLIGHT: Color = Color::Fixed(39);
+
+    /// Draw `board`, marking the discs on the cells in `highlight`
+    fn write(f: &mut std::fmt::Formatter<'_>, board: &Board, highlight: u64) -> std::fmt::Result {
         #[cfg(target_os = "windows")]
         ansi_term::enable_ansi_support().expect("ANSI colors not supported");
 
@@ -35,15 +40,21 @@ impl std::fmt::Display for BoardAnsiWriter {
         let empty = Self::BACKGROUND.paint("●").to_string();
         let red = Self::BACKGROUND.fg(Self::RED).paint("◉").to_string();
         let yellow = Self::BACKGROUND.fg(Self::YELLOW).paint("◉").to_string();
+        let disc = |color: Color| Self::BACKGROUND.on(Self::HIGHLIGHT).fg(color).paint("◉");
+        let (red_win, yellow_win) = (disc(Self::RED).to_string(), disc(Self::YELLOW).to_string());
 
         writeln!(f, "   A B C D E F G ").unwrap();
-        let Self(board) = self;
         ((0..Board::ROWS).rev()).try_for_each(|r| {
             let line: String = itertools::intersperse(
-                (0..Board::COLS).map(|c| match board[(r, c)] {
-                    Cell::Occupied(Player::Red) => red.clone(),
-                    Cell::Occupied(Player::Yellow) => yellow.clone(),
-                    Cell::Empty => empty.clone(),
+                (0..Board::COLS).map(|c| {
+                    let highlighted = highlight & (1 << (r * Board::COLS + c)) != 0;
+                    match (board[(r, c)], highlighted) {
+                        (Cell::Occupied(Player::Red), false) => red.clone(),
+                        (Cell::Occupied(Player::Red), true) => red_win.clone(),
+                        (Cell::Occupied(Player::Yellow), false) => yellow.clone(),
+                        (Cell::Occupied(Player::Yellow), true) => yellow_win.clone(),
+                        (Cell::Empty, _) => empty.clone(),
+                    }
                 }),
                 space.clone(),
             )
@@ -52,3 +63,20 @@ impl std::fmt::Display for BoardAnsiWriter {
         })
     }
 }
+
+impl std::fmt::Display for BoardAnsiWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(board) = self;
+        Self::write(f, board, 0)
+    }
+}
+
+/// Draws the board of a game state, highlighting the winning line(s)
+pub struct GameStateAnsiWriter(pub GameState);
+
+impl std::fmt::Display for GameStateAnsiWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(state) = self;
+        BoardAnsiWriter::write(f, &state.board, state.winning_cells())
+    }
+}
@@ -0,0 +1,257 @@
+use crate::board::Board;
+use crate::board::Cell;
+use crate::board::Player;
+use crate::lookup::CONNECT_FOURS;
+use crate::state::GameState;
+
+/// Error from parsing a position string. Rows and columns count from 1 in the
+/// order of the string, so row 1 is the top row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// Expected six rows separated by `/` followed by the side to move
+    Syntax(String),
+    /// Cell other than `r`, `y` or `.`
+    InvalidCell { row: usize, col: usize, found: char },
+    /// Disc with an empty cell below it
+    FloatingDisc { row: usize, col: usize },
+    /// Yellow moves first so has as many discs as red or one more
+    DiscCount { red: u32, yellow: u32 },
+    /// The disc counts require the other player to move
+    WrongSideToMove { expected: Player },
+    /// Both players have four in a row
+    BothWin,
+    /// A four in a row was not completed by the last move
+    PlayedAfterWin(Player),
+}
+
+impl std::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionError::Syntax(message) => write!(f, "{}", message),
+            PositionError::InvalidCell { row, col, found } => {
+                write!(f, "invalid cell {:?} in row {} column {}", found, row, col)
+            }
+            PositionError::FloatingDisc { row, col } => {
+                write!(f, "disc in row {} column {} is floating", row, col)
+            }
+            PositionError::DiscCount { red, yellow } => {
+                write!(f, "{} red and {} yellow discs is not possible", red, yellow)
+            }
+            PositionError::WrongSideToMove { expected } => {
+                write!(f, "{:?} should be the side to move", expected)
+            }
+            PositionError::BothWin => write!(f, "both players have four in a row"),
+            PositionError::PlayedAfterWin(player) => {
+                write!(f, "moves were played after {:?} won", player)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Position string with the rows from top to bottom, separated by `/`, using
+/// `r`, `y` and `.` for red, yellow and empty cells, followed by the side to
+/// move, e.g. `......./......./......./......./......./...y... r`
+pub fn format(state: &GameState) -> String {
+    let rows = (0..Board::ROWS)
+        .rev()
+        .map(|r| {
+            (0..Board::COLS)
+                .map(|c| match state.board[(r, c)] {
+                    Cell::Occupied(Player::Red) => 'r',
+                    Cell::Occupied(Player::Yellow) => 'y',
+                    Cell::Empty => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    let side = match state.current_player {
+        Player::Red => 'r',
+        Player::Yellow => 'y',
+    };
+    format!("{} {}", rows, side)
+}
+
+/// Parse and validate a position string written by `format`
+pub fn parse(s: &str) -> Result<GameState, PositionError> {
+    let mut parts = s.split_whitespace();
+    let (Some(rows), Some(side), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(PositionError::Syntax(
+            "expected the rows followed by the side to move".to_string(),
+        ));
+    };
+    let current_player = match side {
+        "r" => Player::Red,
+        "y" => Player::Yellow,
+        _ => {
+            return Err(PositionError::Syntax(format!(
+                "invalid side to move {:?}",
+                side
+            )))
+        }
+    };
+    let rows = rows.split('/').collect::<Vec<_>>();
+    if rows.len() != Board::ROWS {
+        return Err(PositionError::Syntax(format!(
+            "expected {} rows but found {}",
+            Board::ROWS,
+            rows.len()
+        )));
+    }
+
+    let mut board = Board::default();
+    for (i, row) in rows.iter().enumerate() {
+        let r = Board::ROWS - 1 - i;
+        if row.chars().count() != Board::COLS {
+            return Err(PositionError::Syntax(format!(
+                "row {} should have {} cells",
+                i + 1,
+                Board::COLS
+            )));
+        }
+        for (c, cell) in row.chars().enumerate() {
+            let mask = 1 << (r * Board::COLS + c);
+            match cell {
+                'r' => board.red |= mask,
+                'y' => board.yellow |= mask,
+                '.' => {}
+                found => {
+                    return Err(PositionError::InvalidCell {
+                        row: i + 1,
+                        col: c + 1,
+                        found,
+                    })
+                }
+            }
+        }
+    }
+    validate(&board, current_player)?;
+    Ok(GameState::from_board(board, current_player))
+}
+
+fn validate(board: &Board, current_player: Player) -> Result<(), PositionError> {
+    let mask = board.red | board.yellow;
+    for r in 1..Board::ROWS {
+        for c in 0..Board::COLS {
+            let cell = 1 << (r * Board::COLS + c);
+            if mask & cell != 0 && mask & (cell >> Board::COLS) == 0 {
+                return Err(PositionError::FloatingDisc {
+                    row: Board::ROWS - r,
+                    col: c + 1,
+                });
+            }
+        }
+    }
+
+    let (red, yellow) = (board.red.count_ones(), board.yellow.count_ones());
+    let expected = if yellow == red {
+        Player::Yellow
+    } else if yellow == red + 1 {
+        Player::Red
+    } else {
+        return Err(PositionError::DiscCount { red, yellow });
+    };
+    if expected != current_player {
+        return Err(PositionError::WrongSideToMove { expected });
+    }
+
+    let lines = |stones: u64| {
+        CONNECT_FOURS
+            .iter()
+            .filter(|&&line| line & stones == line)
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    let (red_lines, yellow_lines) = (lines(board.red), lines(board.yellow));
+    let (winner, winning_lines) = match (red_lines.is_empty(), yellow_lines.is_empty()) {
+        (true, true) => return Ok(()),
+        (false, false) => return Err(PositionError::BothWin),
+        (false, true) => (Player::Red, red_lines),
+        (true, false) => (Player::Yellow, yellow_lines),
+    };
+    // the winner made the last move, which completed every four in a row and
+    // is on top of its column
+    let last_move = winning_lines.iter().fold(mask, |cells, line| cells & line);
+    let on_top = last_move & !(mask >> Board::COLS);
+    if winner == current_player || on_top == 0 {
+        return Err(PositionError::PlayedAfterWin(winner));
+    }
+    Ok(())
+}
+
+impl std::str::FromStr for GameState {
+    type Err = PositionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn formats_and_parses_positions() {
+        for moves in ["", "4453", "1212121"] {
+            let state = *moves.parse::<Game>().unwrap().state();
+            let position = format(&state);
+            assert!(parse(&position).unwrap() == state, "{}", position);
+        }
+        let state = *"4".parse::<Game>().unwrap().state();
+        assert_eq!(
+            format(&state),
+            "......./......./......./......./......./...y... r"
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_positions() {
+        let cases = [
+            (
+                "......./......./......./......./....... y",
+                "expected 6 rows but found 5",
+            ),
+            (
+                "......./......./......./......./......./...y...",
+                "expected the rows followed by the side to move",
+            ),
+            (
+                "......./......./......./......./......./...x... r",
+                "invalid cell 'x' in row 6 column 4",
+            ),
+            (
+                "......./......./......./......./...y.../....... r",
+                "disc in row 5 column 4 is floating",
+            ),
+            (
+                "......./......./......./......./......./..yy... r",
+                "0 red and 2 yellow discs is not possible",
+            ),
+            (
+                "......./......./......./......./......./...y... y",
+                "Red should be the side to move",
+            ),
+            (
+                "......./......./yr...../yr...../yr...../yr..... y",
+                "both players have four in a row",
+            ),
+            // yellow played on after winning
+            (
+                "......./......./......./......./rrr..../yyyyr.. y",
+                "moves were played after Yellow won",
+            ),
+            // the four in a row is buried under a red disc
+            (
+                "......./r....../y....../y....../yy...../yrrr... r",
+                "moves were played after Yellow won",
+            ),
+        ];
+        for (position, message) in cases {
+            let err = parse(position).err().unwrap();
+            assert_eq!(err.to_string(), message, "{}", position);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::board::Column;
+use crate::board::MoveError;
+use crate::state::GameState;
+
+/// Game keeping the ordered list of moves played so they can be taken back
+#[derive(Clone, Default)]
+pub struct Game {
+    state: GameState,
+    // state before each move of `moves`
+    history: Vec<GameState>,
+    moves: Vec<Column>,
+    // moves taken back, the most recent last
+    undone: Vec<Column>,
+}
+
+impl From<GameState> for Game {
+    fn from(state: GameState) -> Self {
+        Self {
+            state,
+            ..Self::default()
+        }
+    }
+}
+
+impl Game {
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Position the game started from
+    pub fn start(&self) -> &GameState {
+        self.history.first().unwrap_or(&self.state)
+    }
+
+    pub fn moves(&self) -> &[Column] {
+        &self.moves
+    }
+
+    /// Play a move, forgetting any moves that were taken back
+    pub fn apply_move(&mut self, c: Column) {
+        self.undone.clear();
+        self.play(c);
+    }
+
+    /// Play a move if it is legal, forgetting any moves that were taken back
+    pub fn try_apply_move(&mut self, c: Column) -> Result<(), MoveError> {
+        let mut state = self.state;
+        state.try_apply_move(c)?;
+        self.undone.clear();
+        self.history.push(self.state);
+        self.state = state;
+        self.moves.push(c);
+        Ok(())
+    }
+
+    /// Take back the last move, returning it
+    pub fn undo_move(&mut self) -> Option<Column> {
+        let c = self.moves.pop()?;
+        self.state = self.history.pop().unwrap();
+        self.undone.push(c);
+        Some(c)
+    }
+
+    /// Replay the last move taken back, returning it
+    pub fn redo_move(&mut self) -> Option<Column> {
+        let c = self.undone.pop()?;
+        self.play(c);
+        Some(c)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    fn play(&mut self, c: Column) {
+        self.history.push(self.state);
+        self.state.apply_move(c);
+        self.moves.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_walk_the_moves() {
+        let mut game: Game = "4453".parse().unwrap();
+        let before = *game.state();
+        assert_eq!(game.undo_move(), Some(Column::C));
+        assert_eq!(game.undo_move(), Some(Column::E));
+        assert_eq!(game.moves(), &[Column::D, Column::D]);
+        assert!(*game.state() == *"44".parse::<Game>().unwrap().state());
+        assert!(game.can_redo());
+        assert_eq!(game.redo_move(), Some(Column::E));
+        assert_eq!(game.redo_move(), Some(Column::C));
+        assert_eq!(game.redo_move(), None);
+        assert!(*game.state() == before);
+
+        while game.undo_move().is_some() {}
+        assert!(!game.can_undo());
+        assert!(game.state() == game.start());
+        assert!(*game.start() == GameState::default());
+    }
+
+    #[test]
+    fn new_moves_forget_the_undone_ones() {
+        let mut game: Game = "4453".parse().unwrap();
+        game.undo_move();
+        game.apply_move(Column::A);
+        assert!(!game.can_redo());
+        game.undo_move();
+        assert_eq!(game.try_apply_move(Column::B), Ok(()));
+        assert!(!game.can_redo());
+        assert_eq!(game.moves(), &[Column::D, Column::D, Column::E, Column::B]);
+    }
+}
@@ -1,5 +1,13 @@
 pub mod board;
 pub mod display;
+pub mod fen;
+pub mod game;
 pub mod lookup;
+pub mod notation;
+pub mod openings;
+pub mod runner;
+pub mod sprt;
 pub mod state;
 pub mod strategy;
+pub mod tournament;
+pub mod transposition;
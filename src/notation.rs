@@ -0,0 +1,139 @@
+use crate::board::Column;
+use crate::board::MoveError;
+use crate::game::Game;
+
+/// Ways of writing down the moves of a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// 1-based column numbers without separators, e.g. `4453`
+    Numeric,
+    /// Lowercase column letters grouped in pairs of moves, e.g. `dd ec`
+    Letters,
+}
+
+impl Notation {
+    pub fn format(&self, moves: &[Column]) -> String {
+        match self {
+            Notation::Numeric => moves.iter().map(|&c| char::from(b'1' + c as u8)).collect(),
+            Notation::Letters => moves
+                .chunks(2)
+                .map(|pair| pair.iter().map(|&c| char::from(b'a' + c as u8)).collect())
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Error from parsing a sequence of moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// Character at byte `index` is not a column
+    InvalidCharacter { index: usize, found: char },
+    /// Move number `index` (starting at 0) was played in a full column
+    ColumnFull { index: usize, column: Column },
+    /// Move number `index` (starting at 0) was played after the game ended
+    GameOver { index: usize },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidCharacter { index, found } => {
+                write!(f, "invalid column {:?} at position {}", found, index)
+            }
+            ParseError::ColumnFull { index, column } => {
+                write!(
+                    f,
+                    "move {} is played in full column {:?}",
+                    index + 1,
+                    column
+                )
+            }
+            ParseError::GameOver { index } => {
+                write!(f, "move {} is played after the game ended", index + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Column for a 1-based digit or a letter in either case
+pub(crate) fn parse_column(c: char) -> Option<Column> {
+    let index = match c {
+        '1'..='7' => c as usize - '1' as usize,
+        'a'..='g' => c as usize - 'a' as usize,
+        'A'..='G' => c as usize - 'A' as usize,
+        _ => return None,
+    };
+    Some(Column::ALL[index])
+}
+
+/// Parse moves written in any `Notation`, ignoring whitespace, and play them
+/// from the start of a game
+pub fn parse(s: &str) -> Result<Game, ParseError> {
+    let mut game = Game::default();
+    let columns = s.char_indices().filter(|(_, c)| !c.is_whitespace());
+    for (index, (i, c)) in columns.enumerate() {
+        let column = parse_column(c).ok_or(ParseError::InvalidCharacter { index: i, found: c })?;
+        game.try_apply_move(column).map_err(|e| match e {
+            MoveError::ColumnFull(column) => ParseError::ColumnFull { index, column },
+            MoveError::GameOver => ParseError::GameOver { index },
+        })?;
+    }
+    Ok(game)
+}
+
+impl std::str::FromStr for Game {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_both_notations() {
+        let moves = [Column::D, Column::D, Column::E, Column::C, Column::A];
+        let numeric = Notation::Numeric.format(&moves);
+        let letters = Notation::Letters.format(&moves);
+        assert_eq!(numeric, "44531");
+        assert_eq!(letters, "dd ec a");
+        assert_eq!(parse(&numeric).unwrap().moves(), &moves);
+        assert_eq!(parse(&letters).unwrap().moves(), &moves);
+        assert_eq!(parse("D d\n5 C 1").unwrap().moves(), &moves);
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        // the index is in bytes, past the three bytes of an ideographic space
+        let err = parse("4\u{3000}h").err();
+        assert_eq!(
+            err,
+            Some(ParseError::InvalidCharacter {
+                index: 4,
+                found: 'h'
+            })
+        );
+        let err = parse("4 0").err();
+        assert_eq!(
+            err,
+            Some(ParseError::InvalidCharacter {
+                index: 2,
+                found: '0'
+            })
+        );
+        let err = parse("1111111").err();
+        let full = ParseError::ColumnFull {
+            index: 6,
+            column: Column::A,
+        };
+        assert_eq!(err, Some(full));
+        assert_eq!(full.to_string(), "move 7 is played in full column A");
+        let err = parse("12121212").err();
+        assert_eq!(err, Some(ParseError::GameOver { index: 7 }));
+    }
+}
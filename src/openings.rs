@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use crate::board::Board;
+use crate::state::GameState;
+use crate::strategy::solver::Solver;
+
+fn mirror(board: &Board) -> Board {
+    let mut mirrored = Board::default();
+    for r in 0..Board::ROWS {
+        for c in 0..Board::COLS {
+            let from = 1 << (r * Board::COLS + c);
+            let to = 1 << (r * Board::COLS + Board::COLS - 1 - c);
+            if board.red & from != 0 {
+                mirrored.red |= to;
+            }
+            if board.yellow & from != 0 {
+                mirrored.yellow |= to;
+            }
+        }
+    }
+    mirrored
+}
+
+/// Set of starting positions for engine matches
+#[derive(Clone)]
+pub struct Openings {
+    positions: Vec<GameState>,
+}
+
+impl Default for Openings {
+    /// Every 2 to 4 ply opening in which neither side can force a win within
+    /// the next 8 plies
+    fn default() -> Self {
+        Self::balanced(2..=4, 8)
+    }
+}
+
+impl Openings {
+    pub fn new(positions: Vec<GameState>) -> Self {
+        assert!(!positions.is_empty(), "no opening positions");
+        Self { positions }
+    }
+
+    /// Every position after exactly `plies` moves that is not already over,
+    /// without transpositions or mirror images
+    pub fn all(plies: usize) -> Self {
+        let mut positions = vec![GameState::default()];
+        for _ in 0..plies {
+            let mut seen = HashSet::new();
+            positions = positions
+                .iter()
+                .flat_map(|state| {
+                    state.possible_moves().into_iter().map(move |c| {
+                        let mut s = *state;
+                        s.apply_move(c);
+                        s
+                    })
+                })
+                .filter(|s| !s.state.is_over())
+                .filter(|s| seen.insert(s.board.key().min(mirror(&s.board).key())))
+                .collect();
+        }
+        Self::new(positions)
+    }
+
+    /// Openings of every length in `plies` where neither side can force a win
+    /// within `horizon` plies according to the solver
+    pub fn balanced(plies: RangeInclusive<usize>, horizon: u8) -> Self {
+        let positions = plies
+            .flat_map(|n| Self::all(n).positions)
+            .filter(|state| !Solver::is_decided_within(state, horizon))
+            .collect();
+        Self::new(positions)
+    }
+
+    /// Keep only the openings accepted by `predicate`, e.g. an evaluation
+    pub fn filter<F>(self, mut predicate: F) -> Self
+    where
+        F: FnMut(&GameState) -> bool,
+    {
+        Self::new(
+            self.positions
+                .into_iter()
+                .filter(|s| predicate(s))
+                .collect(),
+        )
+    }
+
+    pub fn positions(&self) -> &[GameState] {
+        &self.positions
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Opening for the `n`th game pair, cycling through the whole set
+    pub fn get(&self, n: usize) -> GameState {
+        self.positions[n % self.positions.len()]
+    }
+}
+
+impl From<GameState> for Openings {
+    fn from(state: GameState) -> Self {
+        Self::new(vec![state])
+    }
+}
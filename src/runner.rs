@@ -0,0 +1,207 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::board::Column;
+use crate::board::MoveError;
+use crate::board::Player;
+use crate::state::GameState;
+use crate::state::State;
+use crate::strategy::Agent;
+
+/// Time each player is allowed to think. Agents only see the clock through
+/// `Agent::next_move_with_clock`, so those that ignore it need a budget that
+/// fits the control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    Unlimited,
+    /// Base time for the whole game plus an increment added after every move
+    Increment {
+        base: Duration,
+        increment: Duration,
+    },
+    /// Fixed time for every move, unused time is not carried over
+    PerMove(Duration),
+}
+
+/// Chess style clock tracking the remaining time of both players
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    control: TimeControl,
+    red: Duration,
+    yellow: Duration,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let time = match control {
+            TimeControl::Unlimited => Duration::MAX,
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(time) => time,
+        };
+        Self {
+            control,
+            red: time,
+            yellow: time,
+        }
+    }
+
+    /// Time left for the next move of `player`
+    pub fn remaining(&self, player: Player) -> Duration {
+        match player {
+            Player::Red => self.red,
+            Player::Yellow => self.yellow,
+        }
+    }
+
+    /// Time `player` can spend on their next move: a share of the time left
+    /// plus the increment, or most of the time per move, keeping a margin for
+    /// the time taken outside the search. `None` when time is unlimited.
+    pub fn allotment(&self, player: Player) -> Option<Duration> {
+        let remaining = self.remaining(player);
+        match self.control {
+            TimeControl::Unlimited => None,
+            TimeControl::Increment { increment, .. } => {
+                Some((remaining / 20 + increment).min(remaining / 2))
+            }
+            TimeControl::PerMove(_) => Some(remaining.mul_f64(0.9)),
+        }
+    }
+
+    /// Charge a move taking `elapsed` to `player`, returning false when they
+    /// ran out of time
+    pub fn charge(&mut self, player: Player, elapsed: Duration) -> bool {
+        let remaining = match player {
+            Player::Red => &mut self.red,
+            Player::Yellow => &mut self.yellow,
+        };
+        let Some(left) = remaining.checked_sub(elapsed) else {
+            *remaining = Duration::ZERO;
+            return false;
+        };
+        *remaining = match self.control {
+            TimeControl::Unlimited => left,
+            TimeControl::Increment { increment, .. } => left.saturating_add(increment),
+            TimeControl::PerMove(time) => time,
+        };
+        true
+    }
+}
+
+/// Reason a player lost without the game being played out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forfeit {
+    Time,
+    IllegalMove(Column, MoveError),
+}
+
+/// Record of a finished game
+#[derive(Clone)]
+pub struct GameResult {
+    /// Position the game ended in
+    pub state: GameState,
+    pub moves: Vec<Column>,
+    /// Time used by each move
+    pub times: Vec<Duration>,
+    /// Player who lost on time or by playing an illegal move, if any
+    pub forfeit: Option<(Player, Forfeit)>,
+}
+
+impl GameResult {
+    /// Outcome of the game, counting a forfeit as a win for the opponent
+    pub fn result(&self) -> State {
+        match self.forfeit {
+            Some((player, _)) => State::Win(!player),
+            None => self.state.state,
+        }
+    }
+}
+
+/// Drives two agents through a game under a time control
+pub struct Runner<'a> {
+    yellow: &'a dyn Agent,
+    red: &'a dyn Agent,
+    control: TimeControl,
+}
+
+impl<'a> Runner<'a> {
+    pub fn new(yellow: &'a dyn Agent, red: &'a dyn Agent, control: TimeControl) -> Self {
+        Self {
+            yellow,
+            red,
+            control,
+        }
+    }
+
+    pub fn play(&self, state: GameState) -> GameResult {
+        self.play_with(state, |_, _| {})
+    }
+
+    /// Play a game from `state`, calling `on_move` after every move
+    pub fn play_with<F>(&self, mut state: GameState, mut on_move: F) -> GameResult
+    where
+        F: FnMut(&GameState, Column),
+    {
+        let mut clock = Clock::new(self.control);
+        let mut moves = vec![];
+        let mut times = vec![];
+        let mut forfeit = None;
+        while !state.state.is_over() {
+            let player = state.current_player;
+            let agent = match player {
+                Player::Red => self.red,
+                Player::Yellow => self.yellow,
+            };
+            let start = Instant::now();
+            let c = agent.next_move_with_clock(&state, &clock);
+            let elapsed = start.elapsed();
+            times.push(elapsed);
+            if !clock.charge(player, elapsed) {
+                forfeit = Some((player, Forfeit::Time));
+                break;
+            }
+            if let Err(e) = state.try_apply_move(c) {
+                forfeit = Some((player, Forfeit::IllegalMove(c, e)));
+                break;
+            }
+            moves.push(c);
+            on_move(&state, c);
+        }
+        GameResult {
+            state,
+            moves,
+            times,
+            forfeit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::budget::Budget;
+    use crate::strategy::mcts::MctsAgent;
+    use crate::strategy::parallel::ParallelMctsAgent;
+
+    #[test]
+    fn searching_agents_keep_to_the_clock() {
+        let state = GameState::default();
+        let agent = || MctsAgent::new(Budget::UntilProven, state).with_seed(0);
+        let (yellow, red) = (agent(), agent());
+        let control = TimeControl::PerMove(Duration::from_millis(100));
+        let result = Runner::new(&yellow, &red, control).play(state);
+        assert_eq!(result.forfeit, None, "{:?}", result.times);
+        assert!(result.state.state.is_over());
+    }
+
+    #[test]
+    fn parallel_agents_keep_to_the_clock_with_more_trees_than_threads() {
+        let state = GameState::default();
+        let trees = 4 * rayon::current_num_threads();
+        let agent = || ParallelMctsAgent::new(Budget::UntilProven, trees).with_seed(0);
+        let (yellow, red) = (agent(), agent());
+        let control = TimeControl::PerMove(Duration::from_millis(100));
+        let result = Runner::new(&yellow, &red, control).play(state);
+        assert_eq!(result.forfeit, None, "{:?}", result.times);
+        assert!(result.state.state.is_over());
+    }
+}
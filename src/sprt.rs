@@ -0,0 +1,198 @@
+use crate::openings::Openings;
+use crate::runner::TimeControl;
+use crate::state::GameState;
+use crate::strategy::Agent;
+use crate::tournament::play_pair;
+use crate::tournament::AgentFactory;
+use crate::tournament::Elo;
+use crate::tournament::Record;
+
+/// Outcome of a sequential probability ratio test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The candidate is at most `elo0` stronger
+    AcceptH0,
+    /// The candidate is at least `elo1` stronger
+    AcceptH1,
+    /// Not enough games to decide either way
+    Continue,
+}
+
+/// Sequential probability ratio test of H0: the Elo difference is `elo0`
+/// against H1: the Elo difference is `elo1`, with false positive rate `alpha`
+/// and false negative rate `beta`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        }
+    }
+
+    /// Lower and upper bounds on the log-likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of H1 over H0 given the candidate's record, using
+    /// the normal approximation of the trinomial game outcomes
+    pub fn llr(&self, record: &Record) -> f64 {
+        if record.games() == 0 {
+            return 0.0;
+        }
+        // half a game of every outcome as a prior keeps the variance from
+        // collapsing while some outcome has not been seen yet
+        let [wins, draws, losses] =
+            [record.wins, record.draws, record.losses].map(|n| n as f64 + 0.5);
+        let games = wins + draws + losses;
+        let score = (wins + draws / 2.0) / games;
+        let variance =
+            (wins * (1.0 - score).powi(2) + draws * (0.5 - score).powi(2) + losses * score.powi(2))
+                / games;
+        let s0 = Elo::expected_score(self.elo0);
+        let s1 = Elo::expected_score(self.elo1);
+        games * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn decide(&self, record: &Record) -> Decision {
+        let llr = self.llr(record);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Decision::AcceptH1
+        } else if llr <= lower {
+            Decision::AcceptH0
+        } else {
+            Decision::Continue
+        }
+    }
+}
+
+/// Result of an SPRT match, from the point of view of the candidate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SprtResult {
+    pub record: Record,
+    pub llr: f64,
+    pub decision: Decision,
+}
+
+/// Match between a candidate and a baseline agent playing game pairs until the
+/// test reaches a decision or `max_pairs` pairs were played
+pub struct SprtMatch {
+    candidate: AgentFactory,
+    baseline: AgentFactory,
+    sprt: Sprt,
+    control: TimeControl,
+    max_pairs: usize,
+    openings: Openings,
+}
+
+impl SprtMatch {
+    pub fn new<C, B>(
+        candidate: C,
+        baseline: B,
+        sprt: Sprt,
+        control: TimeControl,
+        max_pairs: usize,
+    ) -> Self
+    where
+        C: Fn() -> Box<dyn Agent> + 'static,
+        B: Fn() -> Box<dyn Agent> + 'static,
+    {
+        Self {
+            candidate: Box::new(candidate),
+            baseline: Box::new(baseline),
+            sprt,
+            control,
+            max_pairs,
+            openings: Openings::from(GameState::default()),
+        }
+    }
+
+    /// Start successive game pairs from successive `openings`
+    pub fn with_openings(mut self, openings: Openings) -> Self {
+        self.openings = openings;
+        self
+    }
+
+    pub fn run(&self) -> SprtResult {
+        self.run_with(|_| {})
+    }
+
+    /// Run the match, calling `on_pair` with the intermediate result after
+    /// every game pair
+    pub fn run_with<F>(&self, mut on_pair: F) -> SprtResult
+    where
+        F: FnMut(&SprtResult),
+    {
+        let mut result = SprtResult {
+            record: Record::default(),
+            llr: 0.0,
+            decision: Decision::Continue,
+        };
+        for n in 0..self.max_pairs {
+            result.record += play_pair(
+                &self.candidate,
+                &self.baseline,
+                self.control,
+                self.openings.get(n),
+            );
+            result.llr = self.sprt.llr(&result.record);
+            result.decision = self.sprt.decide(&result.record);
+            on_pair(&result);
+            if result.decision != Decision::Continue {
+                break;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(wins: u32, draws: u32, losses: u32) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn bounds_follow_the_error_rates() {
+        let (lower, upper) = Sprt::new(0.0, 10.0, 0.05, 0.05).bounds();
+        assert!((lower + 19f64.ln()).abs() < 1e-12, "{}", lower);
+        assert!((upper - 19f64.ln()).abs() < 1e-12, "{}", upper);
+    }
+
+    #[test]
+    fn decides_once_the_ratio_crosses_a_bound() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        assert_eq!(sprt.llr(&Record::default()), 0.0);
+        // wins alone cross the upper bound of ln 19 on the 15th game
+        assert!((sprt.llr(&record(15, 0, 0)) - 2.9653).abs() < 1e-4);
+        assert_eq!(sprt.decide(&record(14, 0, 0)), Decision::Continue);
+        assert_eq!(sprt.decide(&record(15, 0, 0)), Decision::AcceptH1);
+        // draws score below the expected score under H1, so they slowly
+        // favour H0
+        assert!(sprt.llr(&record(0, 10, 0)) < 0.0);
+        assert_eq!(sprt.decide(&record(0, 80, 0)), Decision::Continue);
+        assert_eq!(sprt.decide(&record(0, 85, 0)), Decision::AcceptH0);
+        assert_eq!(sprt.decide(&record(0, 0, 14)), Decision::Continue);
+        assert_eq!(sprt.decide(&record(0, 0, 15)), Decision::AcceptH0);
+    }
+}
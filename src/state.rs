@@ -1,5 +1,6 @@
 use crate::board::Board;
 use crate::board::Column;
+use crate::board::MoveError;
 use crate::board::Player;
 use crate::lookup;
 use crate::lookup::CONNECT_FOURS;
@@ -28,6 +29,8 @@ pub struct GameState {
     possible_reds: u128,
     possible_yellows: u128,
     pub state: State,
+    // indices into CONNECT_FOURS of the lines completed by the winning move
+    winning_lines: u128,
 }
 
 impl Default for GameState {
@@ -38,17 +41,101 @@ impl Default for GameState {
             possible_reds: (1_u128 << CONNECT_FOURS.len()) - 1,
             possible_yellows: (1_u128 << CONNECT_FOURS.len()) - 1,
             state: State::InProgress,
+            winning_lines: 0,
         }
     }
 }
 
 impl GameState {
+    /// Game state for any board with `current_player` to move, without
+    /// checking that the position can be reached
+    pub fn from_board(board: Board, current_player: Player) -> Self {
+        // lines a player can still complete are those without opponent stones
+        let open_lines = |opponent: u64| {
+            CONNECT_FOURS
+                .iter()
+                .enumerate()
+                .filter(|(_, &line)| line & opponent == 0)
+                .fold(0, |lines, (i, _)| lines | 1 << i)
+        };
+        let lines = |stones: u64| {
+            CONNECT_FOURS
+                .iter()
+                .enumerate()
+                .filter(|(_, &line)| line & stones == line)
+                .fold(0, |lines, (i, _)| lines | 1 << i)
+        };
+        let (red_lines, yellow_lines) = (lines(board.red), lines(board.yellow));
+        let (state, winning_lines) = if red_lines != 0 {
+            (State::Win(Player::Red), red_lines)
+        } else if yellow_lines != 0 {
+            (State::Win(Player::Yellow), yellow_lines)
+        } else if board.possible_moves().is_empty() {
+            (State::Draw, 0)
+        } else {
+            (State::InProgress, 0)
+        };
+        Self {
+            board,
+            current_player,
+            possible_reds: open_lines(board.yellow),
+            possible_yellows: open_lines(board.red),
+            state,
+            winning_lines,
+        }
+    }
+
+    /// Every four in a row completed by the winning move, as board masks
+    pub fn winning_lines(&self) -> Vec<u64> {
+        CONNECT_FOURS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.winning_lines & (1 << i) != 0)
+            .map(|(_, &line)| line)
+            .collect()
+    }
+
+    /// Mask of the cells in any winning line
+    pub fn winning_cells(&self) -> u64 {
+        self.winning_lines()
+            .iter()
+            .fold(0, |cells, line| cells | line)
+    }
+
     pub fn possible_moves(&self) -> Vec<Column> {
         self.board.possible_moves()
     }
 
+    /// Columns in which the player to move wins immediately
+    pub fn winning_moves(&self) -> Vec<Column> {
+        if self.state.is_over() {
+            return vec![];
+        }
+        self.board.winning_moves(self.current_player)
+    }
+
+    /// Columns the player to move has to play to block an immediate win of the
+    /// opponent
+    pub fn forced_moves(&self) -> Vec<Column> {
+        if self.state.is_over() {
+            return vec![];
+        }
+        self.board.forced_moves(self.current_player)
+    }
+
     pub fn apply_move(&mut self, c: Column) {
-        let (board, row) = self.board.apply_move(c, self.current_player);
+        if let Err(e) = self.try_apply_move(c) {
+            panic!("Invalid move: {}", e);
+        }
+    }
+
+    /// Play a move for the current player, leaving the state untouched if the
+    /// column is full or the game is already over
+    pub fn try_apply_move(&mut self, c: Column) -> Result<(), MoveError> {
+        if self.state.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        let (board, row) = self.board.try_apply_move(c, self.current_player)?;
         self.board = board;
 
         // update other player's possible moves
@@ -69,7 +156,7 @@ impl GameState {
                 let connect_four = CONNECT_FOURS[index];
                 if player_mask & connect_four == connect_four {
                     self.state = State::Win(self.current_player);
-                    break;
+                    self.winning_lines |= 1 << index;
                 }
             }
             possible >>= 1;
@@ -81,5 +168,6 @@ impl GameState {
             self.state = State::Draw;
         }
         self.current_player = !self.current_player;
+        Ok(())
     }
 }
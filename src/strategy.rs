@@ -1,11 +1,27 @@
 use crate::board::Column;
+use crate::runner::Clock;
 use crate::state::GameState;
 
+mod arena;
+pub mod budget;
 pub mod cli;
 pub mod mcts;
 pub mod mcts2;
+pub mod parallel;
+mod ponder;
 pub mod random;
+mod rave;
+pub mod rollout;
+pub mod selection;
+pub mod solver;
+mod tree;
 
 pub trait Agent {
     fn next_move(&self, board: &GameState) -> Column;
+
+    /// Move played with `clock` running, which agents managing their own time
+    /// use to fit their search in the time left. Others ignore the clock.
+    fn next_move_with_clock(&self, board: &GameState, _clock: &Clock) -> Column {
+        self.next_move(board)
+    }
 }
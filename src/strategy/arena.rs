@@ -0,0 +1,351 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::board::Column;
+use crate::state::GameState;
+
+/// Index of a node in the arena of a search graph
+pub(super) type NodeId = u32;
+
+/// Move from one node of a search graph to another
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Edge {
+    pub column: Column,
+    pub prior: f32,
+    // times the move was chosen from this node, while the child may also be
+    // visited through other moves reaching the same position
+    pub visits: u32,
+    pub child: NodeId,
+}
+
+/// Node stored in an arena, with its edges next to each other
+pub(super) trait ArenaNode: Clone {
+    fn visits(&self) -> u32;
+    fn edges(&self) -> Range<usize>;
+    fn set_edges(&mut self, edges: Range<usize>);
+}
+
+/// Search graph sharing one node between every move order reaching the same
+/// position. Nodes do not keep their game state, which is rebuilt while
+/// descending from the root at index 0.
+pub(super) struct Arena<N> {
+    nodes: Vec<N>,
+    edges: Vec<Edge>,
+    // node of every position by `Board::key`
+    index: HashMap<u64, NodeId>,
+}
+
+impl<N: ArenaNode> Arena<N> {
+    pub(super) fn new(root: N, state: &GameState) -> Self {
+        Self {
+            nodes: vec![root],
+            edges: vec![],
+            index: HashMap::from([(state.board.key(), 0)]),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(super) fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id as usize]
+    }
+
+    pub(super) fn node_mut(&mut self, id: NodeId) -> &mut N {
+        &mut self.nodes[id as usize]
+    }
+
+    pub(super) fn edges(&self, id: NodeId) -> &[Edge] {
+        &self.edges[self.node(id).edges()]
+    }
+
+    pub(super) fn edges_mut(&mut self, id: NodeId) -> &mut [Edge] {
+        let edges = self.node(id).edges();
+        &mut self.edges[edges]
+    }
+
+    /// Node of the position `state`, created with `node` if it is new
+    pub(super) fn get_or_insert(&mut self, state: &GameState, node: impl FnOnce() -> N) -> NodeId {
+        let next = self.nodes.len() as NodeId;
+        let id = *self.index.entry(state.board.key()).or_insert(next);
+        if id == next {
+            self.nodes.push(node());
+        }
+        id
+    }
+
+    /// Node of the position `state`, if it is in the graph
+    pub(super) fn get(&self, state: &GameState) -> Option<NodeId> {
+        self.index.get(&state.board.key()).copied()
+    }
+
+    /// Make `root`, whose position `state` is not in the graph, the root.
+    /// Every other node is kept for the search to link back to, with the old
+    /// root moved to the end, and the old root's new index is returned.
+    pub(super) fn push_root(&mut self, root: N, state: &GameState) -> NodeId {
+        let old = self.nodes.len() as NodeId;
+        let node = std::mem::replace(&mut self.nodes[0], root);
+        self.nodes.push(node);
+        for edge in self.edges.iter_mut().filter(|edge| edge.child == 0) {
+            edge.child = old;
+        }
+        if let Some(id) = self.index.values_mut().find(|id| **id == 0) {
+            *id = old;
+        }
+        self.index.insert(state.board.key(), 0);
+        old
+    }
+
+    /// Give node `id` its outgoing moves
+    pub(super) fn set_edges(&mut self, id: NodeId, edges: impl IntoIterator<Item = Edge>) {
+        let first = self.edges.len();
+        self.edges.extend(edges);
+        let last = self.edges.len();
+        self.node_mut(id).set_edges(first..last);
+    }
+
+    /// Shrink the graph to at most about `target` nodes by turning all but the
+    /// most visited nodes back into leaves, which keep their statistics and
+    /// get expanded again when visited, and dropping the subtrees below them.
+    /// Returns the old index of every kept node like `compact`.
+    pub(super) fn prune(&mut self, target: usize, state: &GameState) -> Vec<NodeId> {
+        let mut expanded: Vec<NodeId> = (1..self.len() as NodeId)
+            .filter(|&id| !self.edges(id).is_empty())
+            .collect();
+        expanded.sort_unstable_by_key(|&id| Reverse(self.node(id).visits()));
+        // every kept node but the root is the child of an expanded one
+        let mut kept = 1 + self.edges(0).len();
+        let mut count = 0;
+        for &id in &expanded {
+            kept += self.edges(id).len();
+            if kept > target {
+                break;
+            }
+            count += 1;
+        }
+        for &id in &expanded[count..] {
+            self.node_mut(id).set_edges(0..0);
+        }
+        self.compact(0, state)
+    }
+
+    /// Drop every node that cannot be reached from `root`, whose position is
+    /// `state`, and make it the root. Returns the old index of every kept
+    /// node, in their new order, so that tables indexed like the nodes can be
+    /// rearranged the same way.
+    pub(super) fn compact(&mut self, root: NodeId, state: &GameState) -> Vec<NodeId> {
+        let mut ids = HashMap::from([(root, 0)]);
+        let mut order = vec![root];
+        let mut index = HashMap::from([(state.board.key(), 0)]);
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut queue = VecDeque::from([(root, *state)]);
+        while let Some((id, state)) = queue.pop_front() {
+            let mut node = self.node(id).clone();
+            let first = edges.len();
+            for edge in self.edges(id) {
+                let child = *ids.entry(edge.child).or_insert_with(|| {
+                    let mut s = state;
+                    s.apply_move(edge.column);
+                    let child = order.len() as NodeId;
+                    order.push(edge.child);
+                    index.insert(s.board.key(), child);
+                    queue.push_back((edge.child, s));
+                    child
+                });
+                edges.push(Edge { child, ..*edge });
+            }
+            node.set_edges(first..edges.len());
+            nodes.push(node);
+        }
+        self.nodes = nodes;
+        self.edges = edges;
+        self.index = index;
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use std::collections::HashSet;
+
+    /// Node remembering its position so that tests can check the links
+    #[derive(Clone)]
+    struct TestNode {
+        key: u64,
+        visits: u32,
+        edges: Range<usize>,
+    }
+
+    impl ArenaNode for TestNode {
+        fn visits(&self) -> u32 {
+            self.visits
+        }
+
+        fn edges(&self) -> Range<usize> {
+            self.edges.clone()
+        }
+
+        fn set_edges(&mut self, edges: Range<usize>) {
+            self.edges = edges;
+        }
+    }
+
+    fn node(state: &GameState) -> TestNode {
+        let key = state.board.key();
+        TestNode {
+            key,
+            // arbitrary but fixed visits
+            visits: (key % 97) as u32,
+            edges: 0..0,
+        }
+    }
+
+    fn state(moves: &str) -> GameState {
+        *moves.parse::<Game>().unwrap().state()
+    }
+
+    /// Graph of every position up to `depth` moves after `root`
+    fn graph(root: &GameState, depth: usize) -> Arena<TestNode> {
+        let mut arena = Arena::new(node(root), root);
+        let mut frontier = vec![(0, *root)];
+        for _ in 0..depth {
+            let mut next = vec![];
+            for (id, state) in frontier {
+                let mut edges = vec![];
+                for column in state.possible_moves() {
+                    let mut s = state;
+                    s.apply_move(column);
+                    let len = arena.len();
+                    let child = arena.get_or_insert(&s, || node(&s));
+                    if arena.len() > len {
+                        next.push((child, s));
+                    }
+                    edges.push(Edge {
+                        column,
+                        prior: 0.0,
+                        visits: 0,
+                        child,
+                    });
+                }
+                arena.set_edges(id, edges);
+            }
+            frontier = next;
+        }
+        arena
+    }
+
+    /// Keys of the positions reachable from `root` by following the edges,
+    /// checking that every edge leads to the node of its position
+    fn reachable(arena: &Arena<TestNode>, root: &GameState) -> HashSet<u64> {
+        let mut keys = HashSet::from([root.board.key()]);
+        let mut queue = VecDeque::from([(0, *root)]);
+        assert_eq!(arena.node(0).key, root.board.key());
+        while let Some((id, state)) = queue.pop_front() {
+            for edge in arena.edges(id) {
+                let mut s = state;
+                s.apply_move(edge.column);
+                assert_eq!(arena.node(edge.child).key, s.board.key());
+                assert_eq!(arena.get(&s), Some(edge.child));
+                if keys.insert(s.board.key()) {
+                    queue.push_back((edge.child, s));
+                }
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn compact_keeps_what_the_new_root_reaches() {
+        let mut arena = graph(&state(""), 3);
+        let old_keys: Vec<u64> = (0..arena.len() as NodeId)
+            .map(|id| arena.node(id).key)
+            .collect();
+        let root = arena.get(&state("4")).unwrap();
+        let order = arena.compact(root, &state("4"));
+
+        let keys = reachable(&arena, &state("4"));
+        assert_eq!(keys.len(), arena.len());
+        // every position up to two moves on from the new root, as two moves by
+        // different players never transpose
+        assert_eq!(arena.len(), 1 + 7 + 49);
+        assert_eq!(order.len(), arena.len());
+        for (id, &old) in order.iter().enumerate() {
+            assert_eq!(arena.node(id as NodeId).key, old_keys[old as usize]);
+        }
+        assert_eq!(arena.get(&state("5")), None);
+    }
+
+    #[test]
+    fn push_root_moves_the_old_root_and_its_links() {
+        let mut arena = graph(&state("4"), 1);
+        // a position before the root linking to it
+        let before = arena.get_or_insert(&state(""), || node(&state("")));
+        let edge = Edge {
+            column: Column::D,
+            prior: 0.0,
+            visits: 0,
+            child: 0,
+        };
+        arena.set_edges(before, [edge]);
+
+        let old = arena.push_root(node(&state("3")), &state("3"));
+        assert_eq!(old as usize, arena.len() - 1);
+        assert_eq!(arena.node(0).key, state("3").board.key());
+        assert_eq!(arena.get(&state("3")), Some(0));
+        assert_eq!(arena.node(old).key, state("4").board.key());
+        assert_eq!(arena.get(&state("4")), Some(old));
+        assert_eq!(arena.edges(before)[0].child, old);
+        assert_eq!(arena.edges(old).len(), 7);
+        for edge in arena.edges(old) {
+            let mut s = state("4");
+            s.apply_move(edge.column);
+            assert_eq!(arena.node(edge.child).key, s.board.key());
+        }
+    }
+
+    #[test]
+    fn prune_keeps_the_most_visited_nodes_expanded() {
+        let mut arena = graph(&state(""), 3);
+        let old: Vec<(u64, u32, bool)> = (0..arena.len() as NodeId)
+            .map(|id| {
+                (
+                    arena.node(id).key,
+                    arena.node(id).visits,
+                    !arena.edges(id).is_empty(),
+                )
+            })
+            .collect();
+        let target = 100;
+        assert!(arena.len() > target);
+        let order = arena.prune(target, &state(""));
+
+        assert!(arena.len() <= target, "{}", arena.len());
+        assert_eq!(reachable(&arena, &state("")).len(), arena.len());
+        for (id, &i) in order.iter().enumerate() {
+            let (key, visits, _) = old[i as usize];
+            assert_eq!(arena.node(id as NodeId).key, key);
+            assert_eq!(arena.node(id as NodeId).visits, visits);
+        }
+        // every node left expanded was visited at least as often as those
+        // turned back into leaves
+        let (expanded, leaves): (Vec<NodeId>, Vec<NodeId>) =
+            (1..arena.len() as NodeId).partition(|&id| !arena.edges(id).is_empty());
+        let least_kept = expanded
+            .iter()
+            .map(|&id| arena.node(id).visits)
+            .min()
+            .unwrap();
+        let pruned: Vec<NodeId> = leaves
+            .into_iter()
+            .filter(|&id| old[order[id as usize] as usize].2)
+            .collect();
+        assert!(!pruned.is_empty());
+        assert!(pruned.iter().all(|&id| arena.node(id).visits <= least_kept));
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Amount of searching an agent may do for each move. Every budget also ends
+/// as soon as the position is proven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Fixed number of search iterations
+    Iterations(usize),
+    /// Wall-clock time spent searching
+    Time(Duration),
+    /// Number of new nodes added to the search tree
+    Nodes(usize),
+    /// Keep searching until the position is proven
+    UntilProven,
+}
+
+impl From<usize> for Budget {
+    fn from(iterations: usize) -> Self {
+        Budget::Iterations(iterations)
+    }
+}
+
+impl From<Duration> for Budget {
+    fn from(time: Duration) -> Self {
+        Budget::Time(time)
+    }
+}
+
+impl Budget {
+    /// Start a search timed from `start`, which also ends once `time` has
+    /// passed, if given
+    pub(super) fn start(self, start: Instant, time: Option<Duration>) -> Tracker {
+        Tracker {
+            budget: self,
+            start,
+            time,
+            iterations: 0,
+        }
+    }
+}
+
+/// Progress of a single search against its budget
+pub(super) struct Tracker {
+    budget: Budget,
+    start: Instant,
+    // time allowed by the clock, on top of the budget
+    time: Option<Duration>,
+    iterations: usize,
+}
+
+impl Tracker {
+    /// Count an iteration, returning false once the budget is used up given
+    /// the number of nodes added so far
+    pub(super) fn next(&mut self, nodes: usize) -> bool {
+        let remaining = match self.budget {
+            Budget::Iterations(iterations) => self.iterations < iterations,
+            Budget::Time(time) => self.start.elapsed() < time,
+            Budget::Nodes(limit) => nodes < limit,
+            Budget::UntilProven => true,
+        };
+        self.iterations += 1;
+        remaining && self.time.is_none_or(|time| self.start.elapsed() < time)
+    }
+}
@@ -1,28 +1,45 @@
 use crate::board::Column;
+use crate::notation::parse_column;
 use crate::state::GameState;
 
 use super::Agent;
 
+/// Agent asking for moves on standard input, as a column number or letter.
+/// Ends the process once the input is closed or cannot be read, as no move
+/// can be made without it.
 #[derive(Default)]
 pub struct CliAgent {}
 
 impl Agent for CliAgent {
     fn next_move(&self, state: &GameState) -> Column {
-        let valid_moves = state.possible_moves();
         let mut input = String::new();
-        let mut col = None;
-        while col.is_none() {
+        loop {
             println!("Enter column: ");
-            std::io::stdin().read_line(&mut input).unwrap();
-            col = input.trim().parse::<Column>().ok().and_then(|c| {
-                if !valid_moves.contains(&c) {
-                    println!("Invalid move");
-                    None
-                } else {
-                    Some(c)
+            input.clear();
+            match std::io::stdin().read_line(&mut input) {
+                Ok(0) => {
+                    println!("Input closed");
+                    std::process::exit(0);
                 }
-            });
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Failed to read input: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            let chars = input.trim().chars().collect::<Vec<_>>();
+            let Some(c) = (match chars[..] {
+                [c] => parse_column(c),
+                _ => None,
+            }) else {
+                println!("Invalid column {:?}, enter 1-7 or a-g", input.trim());
+                continue;
+            };
+            let mut s = *state;
+            match s.try_apply_move(c) {
+                Ok(()) => return c,
+                Err(e) => println!("Invalid move: {}", e),
+            }
         }
-        col.unwrap()
     }
 }
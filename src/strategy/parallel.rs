@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use rayon::prelude::*;
+
+use crate::board::Column;
+use crate::runner::Clock;
+use crate::state::GameState;
+
+use super::budget::Budget;
+use super::mcts::Stats;
+use super::rollout::RolloutPolicy;
+use super::selection::Selection;
+use super::tree::print_scores;
+use super::tree::Score;
+use super::tree::SearchConfig;
+use super::tree::SearchTree;
+use super::Agent;
+
+/// Root parallel MCTS running independent search trees on the rayon thread
+/// pool and merging the statistics of their root moves
+pub struct ParallelMctsAgent {
+    budget: Budget,
+    trees: usize,
+    config: SearchConfig,
+}
+
+impl ParallelMctsAgent {
+    /// Search `trees` trees, each with the full budget, on every move. Time
+    /// budgets run from the start of the move for every tree, so trees waiting
+    /// for a thread get less of it.
+    pub fn new(budget: impl Into<Budget>, trees: usize) -> Self {
+        Self {
+            budget: budget.into(),
+            trees,
+            config: SearchConfig::default(),
+        }
+    }
+
+    /// Same as `MctsAgent::with_rollout`, for every tree
+    pub fn with_rollout(mut self, rollout: impl RolloutPolicy + 'static) -> Self {
+        self.config.rollout = Arc::new(rollout);
+        self
+    }
+
+    /// Same as `MctsAgent::with_selection`, for every tree
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.config.selection = selection;
+        self
+    }
+
+    /// Same as `MctsAgent::with_rave`, for every tree
+    pub fn with_rave(mut self, k: f64) -> Self {
+        self.config.rave = Some(k);
+        self
+    }
+
+    /// Same as `MctsAgent::with_node_limit`, for every tree
+    pub fn with_node_limit(mut self, nodes: usize) -> Self {
+        self.config.node_limit = Some(nodes);
+        self
+    }
+
+    /// Same as `MctsAgent::with_seed`, giving each tree its own seed derived
+    /// from `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for ParallelMctsAgent {
+    fn default() -> Self {
+        Self::new(100_000, rayon::current_num_threads())
+    }
+}
+
+fn merge(a: Vec<(Column, u64, Score)>, b: Vec<(Column, u64, Score)>) -> Vec<(Column, u64, Score)> {
+    a.into_iter()
+        .zip(b)
+        .map(|((col, a_visits, a), (_, b_visits, b))| {
+            let visits = a_visits + b_visits;
+            let score = match (a, b) {
+                (Score::Unknown(_), Score::Unknown(_)) if visits > 0 => Score::Unknown(
+                    (a.value() * a_visits as f64 + b.value() * b_visits as f64) / visits as f64,
+                ),
+                (Score::Unknown(_), Score::Unknown(_)) => a,
+                // proofs hold in every tree so keep the best one found
+                (Score::Unknown(_), proven) | (proven, Score::Unknown(_)) => proven,
+                (a, b) => a.max(b),
+            };
+            (col, visits, score)
+        })
+        .collect()
+}
+
+impl Agent for ParallelMctsAgent {
+    fn next_move(&self, board: &GameState) -> Column {
+        self.search_move(board, None)
+    }
+
+    /// Ends the search of every tree early if the clock runs low
+    fn next_move_with_clock(&self, board: &GameState, clock: &Clock) -> Column {
+        self.search_move(board, clock.allotment(board.current_player))
+    }
+}
+
+impl ParallelMctsAgent {
+    fn search_move(&self, board: &GameState, time: Option<Duration>) -> Column {
+        let state = *board;
+        // every tree stops at the same deadline, however many wait for a thread
+        let start = Instant::now();
+        let scores = (0..self.trees.max(1))
+            .into_par_iter()
+            .map(|i| {
+                let mut config = self.config.clone();
+                config.seed = config.seed.map(|seed| seed.wrapping_add(i as u64));
+                let mut search_tree = SearchTree::<Stats>::new(state, config);
+                search_tree.search(self.budget, start, time);
+                search_tree.root_scores()
+            })
+            .reduce_with(merge)
+            .unwrap();
+        print_scores(&scores);
+
+        let best = scores.iter().map(|&(_, _, score)| score).max().unwrap();
+        let proven = scores.iter().all(|(_, _, score)| score.is_proven());
+        let (col, _, _) = if matches!(best, Score::Win(_)) || proven {
+            // win as fast as possible or hold out as long as possible
+            scores.iter().max_by_key(|&&(_, _, score)| score)
+        } else {
+            scores
+                .iter()
+                .filter(|(_, _, score)| !matches!(score, Score::Loss(_)))
+                .max_by_key(|&&(_, visits, _)| visits)
+        }
+        .unwrap();
+        *col
+    }
+}
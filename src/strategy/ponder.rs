@@ -0,0 +1,36 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Search running in a background thread while the opponent is thinking,
+/// stopped and waited for when dropped
+pub(super) struct Ponder {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Ponder {
+    /// Run `search` in a background thread, which has to return soon after
+    /// the flag it is given gets set
+    pub(super) fn start(search: impl FnOnce(&AtomicBool) + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::spawn(move || search(&flag));
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            // a panic in the search poisons the tree, which reports it
+            let _ = handle.join();
+        }
+    }
+}
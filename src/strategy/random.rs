@@ -5,19 +5,33 @@ use rand::prelude::*;
 use super::Agent;
 
 /// RandomAgent to select a random valid move given the current GameState
-pub struct RandomAgent {
-    pub rng: RefCell<ThreadRng>,
+pub struct RandomAgent<R = ThreadRng> {
+    pub rng: RefCell<R>,
 }
 
 impl Default for RandomAgent {
     fn default() -> Self {
+        Self::new(rand::rng())
+    }
+}
+
+impl<R: Rng> RandomAgent<R> {
+    /// Draw the moves from `rng`
+    pub fn new(rng: R) -> Self {
         Self {
-            rng: RefCell::new(rand::rng()),
+            rng: RefCell::new(rng),
         }
     }
 }
 
-impl Agent for RandomAgent {
+impl RandomAgent<StdRng> {
+    /// Play the same moves in every game started with the same `seed`
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> Agent for RandomAgent<R> {
     fn next_move(&self, board: &crate::state::GameState) -> crate::board::Column {
         let moves = board.possible_moves();
         moves[self.rng.borrow_mut().random_range(0..moves.len())]
@@ -0,0 +1,48 @@
+use crate::board::Board;
+use crate::board::Column;
+
+/// All-moves-as-first statistics of the moves from a node: how the player to
+/// move fared in every simulation through the node in which they played a
+/// column at any later point, not just right away
+#[derive(Default, Debug, Clone, Copy)]
+pub(super) struct Amaf {
+    visits: [u32; Board::COLS],
+    // sums of the results in [-1, 1]
+    scores: [f32; Board::COLS],
+}
+
+impl Amaf {
+    /// Add a simulation `result` for the player to move to every column in
+    /// the `columns` bit set
+    pub(super) fn update(&mut self, columns: u8, result: f64) {
+        for c in 0..Board::COLS {
+            if columns & (1 << c) != 0 {
+                self.visits[c] += 1;
+                self.scores[c] += result as f32;
+            }
+        }
+    }
+
+    /// Blend `mean`, the result in [0, 1] of the move `c` over its `visits`,
+    /// with the AMAF result of `c`, trusting the latter less as visits grow
+    /// with the equivalence parameter `k`
+    pub(super) fn blend(&self, c: Column, mean: f64, visits: u64, k: f64) -> f64 {
+        let amaf_visits = self.visits[c as usize];
+        if amaf_visits == 0 {
+            return mean;
+        }
+        let amaf = (self.scores[c as usize] as f64 / amaf_visits as f64 + 1.0) / 2.0;
+        let beta = (k / (3.0 * visits as f64 + k)).sqrt();
+        (1.0 - beta) * mean + beta * amaf
+    }
+}
+
+/// Bit sets of the columns played from each ply of `moves` onwards by the
+/// player making that ply, with room for two plies past the end
+pub(super) fn played_columns(moves: &[Column]) -> Vec<u8> {
+    let mut played = vec![0; moves.len() + 2];
+    for (i, &c) in moves.iter().enumerate().rev() {
+        played[i] = (1 << c as u8) | played[i + 2];
+    }
+    played
+}
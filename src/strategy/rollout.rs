@@ -0,0 +1,63 @@
+use rand::prelude::*;
+use rand::RngCore;
+
+use crate::board::Board;
+use crate::board::Column;
+use crate::state::GameState;
+
+// relative weights of the columns for a center biased rollout, by the number
+// of lines of four running through each column
+pub(super) const CENTER_WEIGHTS: [u32; Board::COLS] = [3, 4, 5, 7, 5, 4, 3];
+
+/// Chooses the moves played during an MCTS simulation
+pub trait RolloutPolicy: Send + Sync {
+    fn choose(&self, state: &GameState, rng: &mut dyn RngCore) -> Column;
+}
+
+/// Plays every possible move with the same probability
+#[derive(Default, Clone, Copy)]
+pub struct Uniform;
+
+impl RolloutPolicy for Uniform {
+    fn choose(&self, state: &GameState, rng: &mut dyn RngCore) -> Column {
+        let moves = state.possible_moves();
+        moves[rng.random_range(0..moves.len())]
+    }
+}
+
+/// Takes immediate wins and blocks immediate losses, playing randomly
+/// otherwise, optionally favoring the center columns
+#[derive(Default, Clone, Copy)]
+pub struct Tactical {
+    center_bias: bool,
+}
+
+impl Tactical {
+    pub fn new(center_bias: bool) -> Self {
+        Self { center_bias }
+    }
+}
+
+impl RolloutPolicy for Tactical {
+    fn choose(&self, state: &GameState, rng: &mut dyn RngCore) -> Column {
+        let board = &state.board;
+        let playable = board.playable();
+        let wins = board.threats(state.current_player) & playable;
+        if let Some(&c) = Board::columns(wins).first() {
+            return c;
+        }
+        let blocks = board.threats(!state.current_player) & playable;
+        if let Some(&c) = Board::columns(blocks).first() {
+            return c;
+        }
+
+        let moves = state.possible_moves();
+        if self.center_bias {
+            *moves
+                .choose_weighted(rng, |&c| CENTER_WEIGHTS[c as usize])
+                .unwrap()
+        } else {
+            moves[rng.random_range(0..moves.len())]
+        }
+    }
+}
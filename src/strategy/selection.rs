@@ -0,0 +1,99 @@
+use crate::board::column_mask;
+use crate::board::Board;
+use crate::state::GameState;
+
+use super::rollout::CENTER_WEIGHTS;
+
+/// Prior probabilities given to the moves of a node when it is expanded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Priors {
+    /// Every move is equally likely
+    Uniform,
+    /// Winning and blocking moves first, then central columns, and moves
+    /// giving the opponent a win right above last
+    Heuristic,
+}
+
+/// Rule picking the child to descend into during selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    /// UCB1 with exploration constant `c`
+    Uct(f64),
+    /// UCB1 with the exploration term scaled by the variance of the results
+    Ucb1Tuned,
+    /// Exploration guided by move priors with constant `c`, as in AlphaZero
+    Puct(f64, Priors),
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Selection::Uct(1.8)
+    }
+}
+
+/// Search statistics of a child node, with results scaled to [0, 1]
+pub(super) struct ChildStats {
+    pub visits: u64,
+    pub mean: f64,
+    // mean of the squared results in [-1, 1], which do not depend on the
+    // point of view
+    pub squares: f64,
+    pub prior: f64,
+}
+
+impl Selection {
+    /// Priority of descending into `child` below a node visited `parent_visits`
+    /// times, where higher is better
+    pub(super) fn priority(&self, child: ChildStats, parent_visits: u64) -> f64 {
+        let parent_visits = parent_visits as f64;
+        let visits = child.visits as f64;
+        match *self {
+            Selection::Puct(c, _) => {
+                let mean = if child.visits == 0 { 0.5 } else { child.mean };
+                mean + c * child.prior * parent_visits.sqrt() / (1.0 + visits)
+            }
+            _ if child.visits == 0 => f64::MAX,
+            Selection::Uct(c) => child.mean + c * (parent_visits.ln() / visits).sqrt(),
+            Selection::Ucb1Tuned => {
+                let log = parent_visits.ln();
+                // variance of the results scaled to [0, 1]
+                let variance = (child.squares - (2.0 * child.mean - 1.0).powi(2)) / 4.0;
+                let bound = variance.max(0.0) + (2.0 * log / visits).sqrt();
+                child.mean + (log / visits * bound.min(0.25)).sqrt()
+            }
+        }
+    }
+
+    /// Prior probabilities of the possible moves of `state`, in the order of
+    /// `GameState::possible_moves`
+    pub(super) fn priors(&self, state: &GameState) -> Vec<f64> {
+        let moves = state.possible_moves();
+        let weights: Vec<f64> = match self {
+            Selection::Puct(_, Priors::Heuristic) => {
+                let board = &state.board;
+                let playable = board.playable();
+                let wins = board.threats(state.current_player) & playable;
+                let losses = board.threats(!state.current_player);
+                let blocks = losses & playable;
+                moves
+                    .iter()
+                    .map(|&c| {
+                        let cell = playable & column_mask(c as usize);
+                        if cell & wins != 0 {
+                            100.0
+                        } else if cell & blocks != 0 {
+                            50.0
+                        } else if (cell << Board::COLS) & losses != 0 {
+                            0.5
+                        } else {
+                            CENTER_WEIGHTS[c as usize] as f64
+                        }
+                    })
+                    .collect()
+            }
+            _ => vec![1.0; moves.len()],
+        };
+        let total: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / total).collect()
+    }
+}
@@ -0,0 +1,516 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use crate::board::column_mask;
+use crate::board::threat_cells;
+use crate::board::Board;
+use crate::board::Column;
+use crate::board::Player;
+use crate::state::GameState;
+use crate::state::State;
+use crate::transposition::Replacement;
+use crate::transposition::TranspositionTable;
+
+use super::Agent;
+
+const CELLS: i32 = (Board::ROWS * Board::COLS) as i32;
+const MIN_SCORE: i32 = -CELLS / 2 + 3;
+
+// stones on the board from which `SolverAgent` solves positions exactly, which
+// then takes well under a second
+const EXACT_FROM: u32 = 12;
+// plies `SolverAgent` looks ahead in earlier positions
+const SHALLOW_DEPTH: u8 = 16;
+
+const MOVE_ORDER: [Column; 7] = [
+    Column::D,
+    Column::C,
+    Column::E,
+    Column::B,
+    Column::F,
+    Column::A,
+    Column::G,
+];
+
+/// Game-theoretic value of a position for the player to move, along with the
+/// number of plies left until the game ends under perfect play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Value {
+    Loss(u8),
+    Draw(u8),
+    Win(u8),
+}
+
+impl Value {
+    /// Value of the position one ply earlier, for the player who made the move
+    pub fn backup(self) -> Self {
+        match self {
+            Value::Loss(n) => Value::Win(n + 1),
+            Value::Draw(n) => Value::Draw(n + 1),
+            Value::Win(n) => Value::Loss(n + 1),
+        }
+    }
+
+    pub fn plies(&self) -> u8 {
+        match *self {
+            Value::Loss(n) | Value::Draw(n) | Value::Win(n) => n,
+        }
+    }
+}
+
+impl Ord for Value {
+    // quick wins are best, quick losses are worst and longer draws are
+    // preferred as they leave the opponent more room to go wrong
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Win(a), Value::Win(b)) => b.cmp(a),
+            (Value::Win(_), _) => Ordering::Greater,
+            (_, Value::Win(_)) => Ordering::Less,
+            (Value::Draw(a), Value::Draw(b)) => a.cmp(b),
+            (Value::Draw(_), _) => Ordering::Greater,
+            (_, Value::Draw(_)) => Ordering::Less,
+            (Value::Loss(a), Value::Loss(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Position {
+    current: u64,
+    mask: u64,
+    moves: i32,
+}
+
+impl From<&GameState> for Position {
+    fn from(state: &GameState) -> Self {
+        let Board { red, yellow } = state.board;
+        let current = match state.current_player {
+            Player::Red => red,
+            Player::Yellow => yellow,
+        };
+        Self {
+            current,
+            mask: red | yellow,
+            moves: (red | yellow).count_ones() as i32,
+        }
+    }
+}
+
+impl Position {
+    fn play(&self, cell: u64) -> Self {
+        Self {
+            current: self.current ^ self.mask,
+            mask: self.mask | cell,
+            moves: self.moves + 1,
+        }
+    }
+
+    fn can_win_next(&self) -> bool {
+        threat_cells(self.current, self.mask) & self.board().playable() != 0
+    }
+
+    fn possible_non_losing_moves(&self) -> u64 {
+        let mut possible = self.board().playable();
+        let opponent_wins = threat_cells(self.current ^ self.mask, self.mask);
+        let forced = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                return 0;
+            }
+            possible = forced;
+        }
+        // never play directly below a cell the opponent wins on
+        possible & !(opponent_wins >> Board::COLS)
+    }
+
+    fn move_score(&self, cell: u64) -> u32 {
+        threat_cells(self.current | cell, self.mask).count_ones()
+    }
+
+    /// Cells of `next` with their move scores, best first
+    fn ordered_moves(&self, next: u64) -> ([(u32, u64); 7], usize) {
+        let mut moves = [(0, 0); 7];
+        let mut count = 0;
+        for c in MOVE_ORDER {
+            let cell = next & column_mask(c as usize);
+            if cell != 0 {
+                moves[count] = (self.move_score(cell), cell);
+                count += 1;
+            }
+        }
+        moves[..count].sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        (moves, count)
+    }
+
+    /// Negamax looking at most `depth` plies ahead, scoring positions that are
+    /// not decided by then as 0
+    fn negamax_within(&self, depth: u8, mut alpha: i32, beta: i32) -> i32 {
+        if depth == 0 {
+            return 0;
+        }
+        if self.can_win_next() {
+            return (CELLS + 1 - self.moves) / 2;
+        }
+        if depth == 1 {
+            return 0;
+        }
+        let next = self.possible_non_losing_moves();
+        if next == 0 {
+            return -(CELLS - self.moves) / 2;
+        }
+        if self.moves >= CELLS - 2 {
+            return 0;
+        }
+
+        let (moves, count) = self.ordered_moves(next);
+        for &(_, cell) in moves[..count].iter() {
+            let score = -self.play(cell).negamax_within(depth - 1, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        alpha
+    }
+
+    fn board(&self) -> Board {
+        // yellow always moves first
+        let (red, yellow) = if self.moves % 2 == 0 {
+            (self.current ^ self.mask, self.current)
+        } else {
+            (self.current, self.current ^ self.mask)
+        };
+        Board { red, yellow }
+    }
+
+    fn value(&self, score: i32) -> Value {
+        let half = CELLS / 2 + 1;
+        let own = self.moves / 2;
+        let opponent = self.moves - own;
+        match score.cmp(&0) {
+            Ordering::Greater => Value::Win((2 * (half - score - own) - 1) as u8),
+            Ordering::Less => Value::Loss((2 * (half + score - opponent)) as u8),
+            Ordering::Equal => Value::Draw((CELLS - self.moves) as u8),
+        }
+    }
+}
+
+/// Alpha-beta negamax solver computing the exact value of a position
+pub struct Solver {
+    // upper bounds on the score of already searched positions
+    table: TranspositionTable<u8>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new(1 << 22)
+    }
+}
+
+impl Solver {
+    pub fn new(table_size: usize) -> Self {
+        Self {
+            table: TranspositionTable::new(table_size, Replacement::Always),
+        }
+    }
+
+    pub fn solve(&mut self, state: &GameState) -> Value {
+        let position = Position::from(state);
+        match state.state {
+            State::Win(_) => return Value::Loss(0),
+            State::Draw => return Value::Draw(0),
+            State::InProgress => {}
+        }
+        if position.can_win_next() {
+            return Value::Win(1);
+        }
+        position.value(self.score(&position))
+    }
+
+    /// Exact score of a position in progress in which the player to move
+    /// cannot win at once
+    fn score(&mut self, position: &Position) -> i32 {
+        // narrow the score window with null window searches
+        let mut min = -(CELLS - position.moves) / 2;
+        let mut max = (CELLS + 1 - position.moves) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let score = self.negamax(position, med, med + 1);
+            if score <= med {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        min
+    }
+
+    /// Whether either player can force a win within `plies` plies
+    pub fn is_decided_within(state: &GameState, plies: u8) -> bool {
+        if state.state.is_over() {
+            return true;
+        }
+        let position = Position::from(state);
+        position.negamax_within(plies, 0, 1) > 0 || position.negamax_within(plies, -1, 0) < 0
+    }
+
+    /// Best move found looking `depth` plies ahead, counting positions that
+    /// are not decided by then as draws and preferring central columns
+    pub fn best_move_within(state: &GameState, depth: u8) -> Option<Column> {
+        if state.state.is_over() {
+            return None;
+        }
+        let position = Position::from(state);
+        let playable = position.board().playable();
+        let wins = threat_cells(position.current, position.mask) & playable;
+        if wins != 0 {
+            return Some(column_of(wins));
+        }
+        let next = position.possible_non_losing_moves();
+        if next == 0 {
+            return Some(column_of(playable));
+        }
+        let (moves, count) = position.ordered_moves(next);
+        let (mut best, mut alpha) = (moves[0].1, -CELLS);
+        for &(_, cell) in moves[..count].iter() {
+            let child = position.play(cell);
+            let score = -child.negamax_within(depth.saturating_sub(1), -CELLS, -alpha);
+            if score > alpha {
+                (best, alpha) = (cell, score);
+            }
+        }
+        Some(column_of(best))
+    }
+
+    /// Values of every possible move, from the point of view of the player to move
+    pub fn analyze(&mut self, state: &GameState) -> Vec<(Column, Value)> {
+        MOVE_ORDER
+            .iter()
+            .filter(|c| state.board.is_possible(c))
+            .map(|&c| {
+                let mut s = *state;
+                s.apply_move(c);
+                (c, self.solve(&s).backup())
+            })
+            .collect()
+    }
+
+    /// Fastest winning move, or the move that holds out the longest otherwise.
+    /// The position is solved once, after which a single null window search
+    /// per move, mostly answered by the transposition table, finds a move
+    /// reaching its score.
+    pub fn best_move(&mut self, state: &GameState) -> Option<(Column, Value)> {
+        if state.state.is_over() {
+            return None;
+        }
+        let position = Position::from(state);
+        let playable = position.board().playable();
+        let wins = threat_cells(position.current, position.mask) & playable;
+        if wins != 0 {
+            return Some((column_of(wins), Value::Win(1)));
+        }
+        let next = position.possible_non_losing_moves();
+        if next == 0 {
+            // every move loses at once
+            let loss = position.value(-(CELLS - position.moves) / 2);
+            return Some((column_of(playable), loss));
+        }
+        let score = self.score(&position);
+        let (moves, count) = position.ordered_moves(next);
+        let (_, cell) = *moves[..count]
+            .iter()
+            .find(|&&(_, cell)| self.negamax(&position.play(cell), -score, 1 - score) <= -score)
+            .unwrap();
+        Some((column_of(cell), position.value(score)))
+    }
+
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        let next = position.possible_non_losing_moves();
+        if next == 0 {
+            return -(CELLS - position.moves) / 2;
+        }
+        if position.moves >= CELLS - 2 {
+            return 0;
+        }
+
+        // the opponent cannot win on their next move
+        let min = -(CELLS - 2 - position.moves) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        let key = position.board().key();
+        let max = match self.table.get(key) {
+            Some(bound) => bound as i32 + MIN_SCORE - 1,
+            None => (CELLS - 1 - position.moves) / 2,
+        };
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        let (moves, count) = position.ordered_moves(next);
+        for &(_, cell) in moves[..count].iter() {
+            let score = -self.negamax(&position.play(cell), -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        self.table.insert(
+            key,
+            (alpha - MIN_SCORE + 1) as u8,
+            (CELLS - position.moves) as u32,
+        );
+        alpha
+    }
+}
+
+/// Column of the lowest of `cells`
+fn column_of(cells: u64) -> Column {
+    Column::ALL[cells.trailing_zeros() as usize % Board::COLS]
+}
+
+/// Agent playing perfectly using the Solver in late positions. Solving takes
+/// seconds to minutes with only a few stones on the board, so before that the
+/// agent looks a fixed number of plies ahead instead.
+pub struct SolverAgent {
+    solver: RefCell<Solver>,
+    exact_from: u32,
+}
+
+impl Default for SolverAgent {
+    fn default() -> Self {
+        Self {
+            solver: RefCell::default(),
+            exact_from: EXACT_FROM,
+        }
+    }
+}
+
+impl SolverAgent {
+    /// Solve every position with at least `stones` stones on the board, which
+    /// with 0 plays perfectly from the start at the cost of minutes per move
+    pub fn with_exact_from(self, stones: u32) -> Self {
+        Self {
+            exact_from: stones,
+            ..self
+        }
+    }
+}
+
+impl Agent for SolverAgent {
+    fn next_move(&self, state: &GameState) -> Column {
+        let stones = (state.board.red | state.board.yellow).count_ones();
+        if stones < self.exact_from {
+            return Solver::best_move_within(state, SHALLOW_DEPTH).unwrap();
+        }
+        let (col, _) = self.solver.borrow_mut().best_move(state).unwrap();
+        col
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn state(moves: &str) -> GameState {
+        *moves.parse::<Game>().unwrap().state()
+    }
+
+    #[test]
+    fn solves_benchmark_positions() {
+        // positions from Pascal Pons' benchmark set, with the distances
+        // checked by plain minimax
+        let mut solver = Solver::new(1 << 20);
+        let cases = [
+            ("2252576253462244111563365343671351441", Value::Loss(4)),
+            ("7422341735647741166133573473242566", Value::Win(7)),
+            ("23163416124767223154467471272416755633", Value::Draw(4)),
+        ];
+        for (moves, value) in cases {
+            assert_eq!(solver.solve(&state(moves)), value, "{}", moves);
+        }
+    }
+
+    #[test]
+    fn converts_scores_to_plies() {
+        let position = Position::from(&state("4455"));
+        // winning with the last stone of the player to move
+        assert_eq!(position.value(1), Value::Win(37));
+        assert_eq!(position.value(-1), Value::Loss(38));
+        assert_eq!(position.value(0), Value::Draw(38));
+        assert_eq!(Value::Loss(38).backup(), Value::Win(39));
+        assert_eq!(Value::Win(37).plies(), 37);
+    }
+
+    #[test]
+    fn values_finished_games() {
+        let mut solver = Solver::new(1 << 10);
+        assert_eq!(solver.solve(&state("1212121")), Value::Loss(0));
+        assert_eq!(solver.solve(&state("121212")), Value::Win(1));
+    }
+
+    #[test]
+    fn best_move_wins_fastest() {
+        // C and G win at once while the other moves only win later
+        let state = state("445566");
+        let mut solver = Solver::new(1 << 16);
+        let (col, value) = solver.best_move(&state).unwrap();
+        assert_eq!(value, Value::Win(1));
+        assert!(matches!(col, Column::C | Column::G));
+        let moves = solver.analyze(&state);
+        assert!(moves.contains(&(Column::B, Value::Win(3))));
+    }
+
+    #[test]
+    fn best_move_matches_analysis() {
+        let mut solver = Solver::new(1 << 20);
+        let cases = [
+            "7422341735647741166133573473242566",
+            "2252576253462244111563365343671351441",
+            "23163416124767223154467471272416755633",
+        ];
+        for moves in cases {
+            let state = state(moves);
+            let analysis = solver.analyze(&state);
+            let best = analysis.iter().map(|&(_, value)| value).max().unwrap();
+            let (col, value) = solver.best_move(&state).unwrap();
+            assert_eq!(value, best, "{}", moves);
+            assert!(analysis.contains(&(col, value)), "{}", moves);
+        }
+    }
+
+    #[test]
+    fn shallow_search_makes_and_stops_double_threats() {
+        // yellow wins by opening a three on the bottom row, which red has to
+        // stop right away
+        let make = Solver::best_move_within(&state("4455"), 4);
+        assert!(matches!(make, Some(Column::C | Column::F)));
+        let stop = Solver::best_move_within(&state("445"), 4);
+        assert!(matches!(stop, Some(Column::C | Column::F)));
+        assert_eq!(SolverAgent::default().next_move(&state("")), Column::D);
+    }
+}
@@ -0,0 +1,783 @@
+use crate::board::Column;
+use crate::runner::Clock;
+use crate::state::GameState;
+use crate::state::State;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::rngs::SmallRng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+use super::arena::Arena;
+use super::arena::ArenaNode;
+use super::arena::Edge;
+use super::arena::NodeId;
+use super::budget::Budget;
+use super::ponder::Ponder;
+use super::rave::played_columns;
+use super::rave::Amaf;
+use super::rollout::RolloutPolicy;
+use super::rollout::Uniform;
+use super::selection::ChildStats;
+use super::selection::Selection;
+use super::solver::Value;
+use super::Agent;
+
+#[derive(Default, Debug, Clone, Copy, PartialOrd, PartialEq)]
+struct OrderedF64(f64);
+
+impl Eq for OrderedF64 {}
+
+#[allow(clippy::derive_ord_xor_partial_ord)]
+impl Ord for OrderedF64 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.partial_cmp(&other.0).unwrap()
+    }
+}
+
+/// Score of a node for the player who moved into it. Unknown scores hold the
+/// mean simulation result in [-1, 1]; proven scores hold the number of plies
+/// until the game ends.
+#[derive(Debug, Clone, Copy)]
+pub enum Score {
+    Loss(u8),
+    Unknown(f64),
+    Draw(u8),
+    Win(u8),
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Score::Unknown(0.0)
+    }
+}
+
+impl Score {
+    pub(super) fn is_proven(&self) -> bool {
+        !matches!(self, Score::Unknown(_))
+    }
+
+    pub(super) fn value(&self) -> f64 {
+        match *self {
+            Score::Loss(_) => -1.0,
+            Score::Unknown(mean) => mean,
+            Score::Draw(_) => 0.0,
+            Score::Win(_) => 1.0,
+        }
+    }
+
+    fn computed_score(&self, visits: u64) -> f64 {
+        if visits == 0 && !self.is_proven() {
+            f64::MAX
+        } else {
+            (self.value() + 1.0) / 2.0
+        }
+    }
+
+    /// Proven score from a value for the player to move in the node
+    pub(super) fn from_value(value: Value) -> Self {
+        match value {
+            Value::Loss(n) => Score::Win(n),
+            Value::Draw(n) => Score::Draw(n),
+            Value::Win(n) => Score::Loss(n),
+        }
+    }
+
+    /// Proven value for the player to move in the node
+    pub(super) fn to_value(self) -> Option<Value> {
+        match self {
+            Score::Loss(n) => Some(Value::Win(n)),
+            Score::Unknown(_) => None,
+            Score::Draw(n) => Some(Value::Draw(n)),
+            Score::Win(n) => Some(Value::Loss(n)),
+        }
+    }
+
+    /// Proven value of the move into the node for the player making it, with
+    /// plies counted from before the move like `Solver::analyze`
+    pub(super) fn proof(&self) -> Option<Value> {
+        self.to_value().map(Value::backup)
+    }
+
+    /// Score of the parent node when this is the best score among its children
+    fn backup(self) -> Self {
+        match self {
+            Score::Loss(n) => Score::Win(n + 1),
+            Score::Unknown(mean) => Score::Unknown(-mean),
+            Score::Draw(n) => Score::Draw(n + 1),
+            Score::Win(n) => Score::Loss(n + 1),
+        }
+    }
+}
+
+impl Ord for Score {
+    // fast wins first and slow losses before fast ones, with unknown scores
+    // ranked against draws by their mean result
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Score::Win(a), Score::Win(b)) => b.cmp(a),
+            (Score::Win(_), _) => Ordering::Greater,
+            (_, Score::Win(_)) => Ordering::Less,
+            (Score::Loss(a), Score::Loss(b)) => a.cmp(b),
+            (Score::Loss(_), _) => Ordering::Less,
+            (_, Score::Loss(_)) => Ordering::Greater,
+            (Score::Draw(a), Score::Draw(b)) => a.cmp(b),
+            (Score::Unknown(a), Score::Unknown(b)) => a.total_cmp(b),
+            (Score::Unknown(mean), Score::Draw(_)) => {
+                if *mean > 0.0 {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+            (Score::Draw(_), Score::Unknown(_)) => other.cmp(self).reverse(),
+        }
+    }
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Score {}
+
+/// Simulation statistics and proof of a node, the part in which the MCTS
+/// engines differ
+pub trait NodeStats: Clone + Send + 'static {
+    /// Change made by a simulation to the statistics of a node, passed on to
+    /// its parent
+    type Update: Copy;
+
+    /// Statistics of an unvisited node for the position `state`
+    fn new(state: &GameState) -> Self;
+
+    fn visits(&self) -> u32;
+
+    /// Score for the player who moved into the node
+    fn score(&self) -> Score;
+
+    /// Mean of the squared simulation results
+    fn squares(&self) -> f64;
+
+    /// Prove the node with `score` for the player who moved into it
+    fn prove(&mut self, score: Score);
+
+    /// Count a simulation ending in `result`, for the player who moved into
+    /// the node, at this leaf
+    fn update_leaf(&mut self, result: f64) -> Self::Update;
+
+    /// Count a simulation through the node given the update of its child
+    fn update(&mut self, child: Self::Update) -> Self::Update;
+}
+
+#[derive(Clone)]
+struct Node<S> {
+    stats: S,
+    // the moves of the node are the `edges` edges from `first_edge` on
+    first_edge: u32,
+    edges: u8,
+}
+
+impl<S: NodeStats> ArenaNode for Node<S> {
+    fn visits(&self) -> u32 {
+        self.stats.visits()
+    }
+
+    fn edges(&self) -> Range<usize> {
+        let first = self.first_edge as usize;
+        first..first + self.edges as usize
+    }
+
+    fn set_edges(&mut self, edges: Range<usize>) {
+        self.first_edge = edges.start as u32;
+        self.edges = edges.len() as u8;
+    }
+}
+
+impl<S: NodeStats> Node<S> {
+    fn new(state: &GameState) -> Self {
+        Self {
+            stats: S::new(state),
+            first_edge: 0,
+            edges: 0,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.edges == 0
+    }
+
+    fn score(&self) -> Score {
+        self.stats.score()
+    }
+}
+
+/// Play out the game from `state`, appending the moves to `moves`
+fn simulate(
+    mut state: GameState,
+    policy: &dyn RolloutPolicy,
+    rng: &mut dyn RngCore,
+    moves: &mut Vec<Column>,
+) -> State {
+    while !state.state.is_over() {
+        let c = policy.choose(&state, rng);
+        state.apply_move(c);
+        moves.push(c);
+    }
+    state.state
+}
+
+/// Policies shared by the search trees of an agent
+#[derive(Clone)]
+pub(super) struct SearchConfig {
+    pub rollout: Arc<dyn RolloutPolicy>,
+    pub selection: Selection,
+    // RAVE equivalence parameter, if AMAF statistics are blended in
+    pub rave: Option<f64>,
+    // number of nodes past which the least visited ones are dropped
+    pub node_limit: Option<usize>,
+    // seed of the simulations, if they are to be reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            rollout: Arc::new(Uniform),
+            selection: Selection::default(),
+            rave: None,
+            node_limit: None,
+            seed: None,
+        }
+    }
+}
+
+const ROOT: NodeId = 0;
+
+// size at which pondering stops without a node limit, about 100 MB
+const PONDER_NODES: usize = 1 << 20;
+
+/// Search graph with the root as its first node, sharing nodes between
+/// transpositions
+pub(super) struct SearchTree<S> {
+    arena: Arena<Node<S>>,
+    // AMAF statistics of every node, only kept with RAVE enabled
+    amaf: Vec<Amaf>,
+    state: GameState,
+    config: SearchConfig,
+    rng: SmallRng,
+}
+
+impl<S: NodeStats> SearchTree<S> {
+    pub(super) fn new(state: GameState, config: SearchConfig) -> Self {
+        let mut tree = SearchTree {
+            arena: Arena::new(Node::new(&state), &state),
+            amaf: vec![],
+            state,
+            rng: match config.seed {
+                Some(seed) => SmallRng::seed_from_u64(seed),
+                None => SmallRng::from_rng(&mut rand::rng()),
+            },
+            config,
+        };
+        if tree.config.rave.is_some() {
+            tree.amaf.push(Amaf::default());
+        }
+        if !state.state.is_over() {
+            tree.expand(ROOT, &state);
+        }
+        tree
+    }
+
+    fn node(&self, id: NodeId) -> &Node<S> {
+        self.arena.node(id)
+    }
+
+    fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.arena.edges(id).iter().map(|edge| edge.child)
+    }
+
+    /// Search until the budget or `time`, both counted from `start`, is used
+    /// up or the root is proven
+    pub(super) fn search(&mut self, budget: Budget, start: Instant, time: Option<Duration>) {
+        let mut nodes = 0;
+        let mut tracker = budget.start(start, time);
+        while tracker.next(nodes) {
+            if self.node(ROOT).score().is_proven() {
+                break;
+            }
+            nodes += self.step();
+        }
+    }
+
+    /// Search until `stop` is set or the root is proven, staying within the
+    /// node limit or stopping at `PONDER_NODES` nodes without one
+    fn ponder(&mut self, stop: &AtomicBool) {
+        let limit = self.config.node_limit.map_or(PONDER_NODES, |_| usize::MAX);
+        while !stop.load(Ordering::Relaxed)
+            && !self.node(ROOT).score().is_proven()
+            && self.arena.len() < limit
+        {
+            self.step();
+        }
+    }
+
+    /// Visits and score of every move from the root
+    pub(super) fn root_scores(&self) -> Vec<(Column, u64, Score)> {
+        self.arena
+            .edges(ROOT)
+            .iter()
+            .map(|edge| {
+                let child = &self.node(edge.child).stats;
+                (edge.column, child.visits() as u64, child.score())
+            })
+            .collect()
+    }
+
+    /// Move the root to the position `state`, however far below the root it
+    /// is, dropping what can no longer be reached. Returns the number of nodes
+    /// kept, or `None` if the position is not in the graph, in which case it
+    /// becomes a new root and the nodes reachable from the old root stay
+    /// until the next move so that the search can link back to them.
+    fn reroot(&mut self, state: &GameState) -> Option<usize> {
+        let kept = match self.arena.get(state) {
+            Some(id) => {
+                let order = self.arena.compact(id, state);
+                self.rearrange_amaf(&order);
+                Some(self.arena.len())
+            }
+            None => {
+                // drop what earlier jumps left behind, so that repeated jumps
+                // keep no more than one old graph around
+                let order = self.arena.compact(ROOT, &self.state);
+                self.rearrange_amaf(&order);
+                self.arena.push_root(Node::new(state), state);
+                if !self.amaf.is_empty() {
+                    let amaf = std::mem::take(&mut self.amaf[0]);
+                    self.amaf.push(amaf);
+                }
+                None
+            }
+        };
+        self.state = *state;
+        if self.node(ROOT).is_leaf() && !state.state.is_over() {
+            self.expand(ROOT, state);
+        }
+        kept
+    }
+
+    /// Run one simulation, pruning the graph once it outgrows the node limit,
+    /// and return the number of nodes added
+    fn step(&mut self) -> usize {
+        let len = self.arena.len();
+        self.select();
+        let added = self.arena.len() - len;
+        if let Some(limit) = self.config.node_limit {
+            if self.arena.len() > limit {
+                // leave room to grow so that pruning does not run every step
+                let order = self.arena.prune(limit / 2, &self.state);
+                self.rearrange_amaf(&order);
+            }
+        }
+        added
+    }
+
+    /// Reorder the AMAF statistics like the nodes, given the old index of
+    /// every kept node
+    fn rearrange_amaf(&mut self, order: &[NodeId]) {
+        if !self.amaf.is_empty() {
+            self.amaf = order.iter().map(|&i| self.amaf[i as usize]).collect();
+        }
+    }
+
+    /// Add the moves of node `id`, linking to the nodes of positions already
+    /// in the graph
+    fn expand(&mut self, id: NodeId, state: &GameState) {
+        let priors = self.config.selection.priors(state);
+        let edges: Vec<Edge> = state
+            .possible_moves()
+            .into_iter()
+            .zip(priors)
+            .map(|(c, prior)| {
+                let mut s = *state;
+                s.apply_move(c);
+                Edge {
+                    column: c,
+                    prior: prior as f32,
+                    visits: 0,
+                    child: self.arena.get_or_insert(&s, || Node::new(&s)),
+                }
+            })
+            .collect();
+        self.arena.set_edges(id, edges);
+        if self.config.rave.is_some() {
+            self.amaf.resize(self.arena.len(), Amaf::default());
+        }
+        self.update_proof(id);
+    }
+
+    /// Prove a node once a child is a win or every child is proven
+    fn update_proof(&mut self, id: NodeId) {
+        let best = self.children(id).map(|c| self.node(c).score()).max();
+        if let Some(best) = best {
+            let all_proven = self.children(id).all(|c| self.node(c).score().is_proven());
+            if matches!(best, Score::Win(_)) || all_proven {
+                self.arena.node_mut(id).stats.prove(best.backup());
+            }
+        }
+    }
+
+    /// Most visited move that is not proven lost, or the best proven move once
+    /// the root is proven. The search stops as soon as any move is proven a
+    /// win, so the win played is the first one found, not always the fastest.
+    pub(super) fn best_move(&self) -> Column {
+        let edges = self.arena.edges(ROOT).iter();
+        let best = if self.node(ROOT).score().is_proven() {
+            // win as fast as possible or hold out as long as possible
+            edges.max_by_key(|edge| self.node(edge.child).score())
+        } else {
+            edges
+                .filter(|edge| !matches!(self.node(edge.child).score(), Score::Loss(_)))
+                .max_by_key(|edge| self.node(edge.child).stats.visits())
+        };
+        best.unwrap().column
+    }
+
+    /// Position among the edges of node `id` of the move to descend into
+    fn best_child(&self, id: NodeId) -> usize {
+        let parent_visits = self.node(id).stats.visits() as u64;
+        let (best, _) = self
+            .arena
+            .edges(id)
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| !self.node(edge.child).score().is_proven())
+            .max_by_key(|(_, edge)| {
+                let child = &self.node(edge.child).stats;
+                let mut mean = (child.score().value() + 1.0) / 2.0;
+                if let Some(k) = self.config.rave {
+                    let amaf = &self.amaf[id as usize];
+                    mean = amaf.blend(edge.column, mean, edge.visits as u64, k);
+                }
+                let stats = ChildStats {
+                    visits: edge.visits as u64,
+                    mean,
+                    squares: child.squares(),
+                    prior: edge.prior as f64,
+                };
+                OrderedF64(self.config.selection.priority(stats, parent_visits))
+            })
+            .unwrap();
+        best
+    }
+
+    fn select(&mut self) {
+        // nodes above the leaf with the position of the edge taken from each
+        let mut path = vec![];
+        let mut moves = vec![];
+        let mut state = self.state;
+        let mut current = ROOT;
+        loop {
+            if self.node(current).is_leaf() {
+                if self.node(current).stats.visits() == 0 || state.state.is_over() {
+                    break;
+                }
+                self.expand(current, &state);
+            } else {
+                // a child may have been proven through another parent
+                self.update_proof(current);
+            }
+            if self.node(current).score().is_proven() {
+                break;
+            }
+            let e = self.best_child(current);
+            let edge = self.arena.edges(current)[e];
+            state.apply_move(edge.column);
+            path.push((current, e));
+            moves.push(edge.column);
+            current = edge.child;
+            if self.node(current).stats.visits() == 0 {
+                break;
+            }
+        }
+
+        // result for the player who moved into the leaf
+        let leaf = self.node(current).score();
+        let mut result = if leaf.is_proven() {
+            leaf.value()
+        } else {
+            let rollout = self.config.rollout.as_ref();
+            match simulate(state, rollout, &mut self.rng, &mut moves) {
+                State::Win(player) if player == state.current_player => -1.0,
+                State::Win(_) => 1.0,
+                _ => 0.0,
+            }
+        };
+
+        let played = played_columns(&moves);
+        let rave = self.config.rave.is_some();
+        if rave {
+            self.amaf[current as usize].update(played[path.len()], -result);
+        }
+        let mut update = self.arena.node_mut(current).stats.update_leaf(result);
+        for (depth, &(id, e)) in path.iter().enumerate().rev() {
+            result = -result;
+            let edge = &mut self.arena.edges_mut(id)[e];
+            edge.visits += 1;
+            let child = edge.child;
+            if self.node(child).score().is_proven() {
+                self.update_proof(id);
+            }
+            update = self.arena.node_mut(id).stats.update(update);
+            if rave {
+                self.amaf[id as usize].update(played[depth], -result);
+            }
+        }
+    }
+}
+
+pub(super) fn print_scores(scores: &[(Column, u64, Score)]) {
+    for (col, visits, score) in scores {
+        if let Some(value) = score.proof() {
+            println!("{:?} - {:8} - {:?}", col, visits, value);
+        } else {
+            let score = score.computed_score(*visits);
+            println!("{:?} - {:8} - {:3.5}", col, visits, score * 100.0);
+        }
+    }
+}
+
+/// Agent searching a graph kept between moves, with nodes keeping statistics
+/// of type `S`
+pub struct SearchAgent<S> {
+    budget: Budget,
+    search_tree: Arc<Mutex<SearchTree<S>>>,
+    pondering: bool,
+    ponder: RefCell<Option<Ponder>>,
+}
+
+impl<S: NodeStats> SearchAgent<S> {
+    pub fn new(budget: impl Into<Budget>, game_state: GameState) -> Self {
+        let search_tree = SearchTree::new(game_state, SearchConfig::default());
+        Self {
+            budget: budget.into(),
+            search_tree: Arc::new(Mutex::new(search_tree)),
+            pondering: false,
+            ponder: RefCell::new(None),
+        }
+    }
+
+    /// Play the simulations with `rollout` instead of uniformly random moves
+    pub fn with_rollout(self, rollout: impl RolloutPolicy + 'static) -> Self {
+        self.with_config(|config| config.rollout = Arc::new(rollout))
+    }
+
+    /// Descend the tree with `selection` instead of UCT
+    pub fn with_selection(self, selection: Selection) -> Self {
+        self.with_config(|config| config.selection = selection)
+    }
+
+    /// Blend all-moves-as-first results into the move values, weighing them
+    /// as much as the real results of a move after `k` visits
+    pub fn with_rave(self, k: f64) -> Self {
+        self.with_config(|config| config.rave = Some(k))
+    }
+
+    /// Keep the search graph to at most `nodes` nodes, dropping the subtrees
+    /// of the least visited ones whenever it fills up
+    pub fn with_node_limit(self, nodes: usize) -> Self {
+        self.with_config(|config| config.node_limit = Some(nodes))
+    }
+
+    /// Play the same simulations, and so the same moves under an iteration or
+    /// node budget, in every game started with the same `seed`
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.with_config(|config| config.seed = Some(seed))
+    }
+
+    /// Keep searching in a background thread after playing a move, until the
+    /// opponent's reply arrives. Pondering prunes the graph like any search
+    /// under a node limit, and otherwise stops once the graph holds about a
+    /// million nodes.
+    pub fn with_pondering(self) -> Self {
+        Self {
+            pondering: true,
+            ..self
+        }
+    }
+
+    fn with_config(self, change: impl FnOnce(&mut SearchConfig)) -> Self {
+        {
+            let mut search_tree = self.search_tree.lock().unwrap();
+            let mut config = search_tree.config.clone();
+            change(&mut config);
+            *search_tree = SearchTree::new(search_tree.state, config);
+        }
+        self
+    }
+}
+
+impl<S: NodeStats> Agent for SearchAgent<S> {
+    fn next_move(&self, board: &GameState) -> Column {
+        self.search_move(board, None)
+    }
+
+    /// Ends the search early if the clock runs low, whatever the budget
+    fn next_move_with_clock(&self, board: &GameState, clock: &Clock) -> Column {
+        self.search_move(board, clock.allotment(board.current_player))
+    }
+}
+
+impl<S: NodeStats> SearchAgent<S> {
+    fn search_move(&self, board: &GameState, time: Option<Duration>) -> Column {
+        // stop pondering before taking the tree back
+        self.ponder.borrow_mut().take();
+        let mut search_tree = self.search_tree.lock().unwrap();
+        if *board != search_tree.state {
+            let total = search_tree.arena.len();
+            match search_tree.reroot(board) {
+                Some(kept) => println!("reusing {} of {} nodes", kept, total),
+                None => println!("state not found. searching from a new root"),
+            }
+        }
+
+        search_tree.search(self.budget, Instant::now(), time);
+        print_scores(&search_tree.root_scores());
+        let col = search_tree.best_move();
+        if self.pondering {
+            let mut state = *board;
+            state.apply_move(col);
+            search_tree.reroot(&state);
+            drop(search_tree);
+            let search_tree = self.search_tree.clone();
+            *self.ponder.borrow_mut() = Some(Ponder::start(move |stop| {
+                search_tree.lock().unwrap().ponder(stop);
+            }));
+        }
+        // otherwise the tree is only moved on the next call, which keeps it
+        // whole if the opponent's reply or an undo leads anywhere in it
+        col
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::strategy::mcts::Stats;
+    use crate::strategy::solver::Solver;
+    use std::collections::HashMap;
+    use std::mem::discriminant;
+
+    fn state(moves: &str) -> GameState {
+        *moves.parse::<Game>().unwrap().state()
+    }
+
+    fn seeded() -> SearchConfig {
+        SearchConfig {
+            seed: Some(0),
+            ..SearchConfig::default()
+        }
+    }
+
+    #[test]
+    fn orders_scores() {
+        let ascending = [
+            Score::Loss(1),
+            Score::Loss(5),
+            Score::Unknown(-0.5),
+            Score::Unknown(0.0),
+            Score::Draw(2),
+            Score::Draw(4),
+            Score::Unknown(0.5),
+            Score::Win(3),
+            Score::Win(1),
+        ];
+        for pair in ascending.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+        assert_eq!(Score::Win(2).backup(), Score::Loss(3));
+        assert_eq!(Score::Win(2).proof(), Some(Value::Win(3)));
+    }
+
+    #[test]
+    fn proves_late_positions_like_the_solver() {
+        let mut solver = Solver::new(1 << 16);
+        for moves in [
+            "2252576253462244111563365343671351441",
+            "7422341735647741166133573473242566",
+            "23163416124767223154467471272416755633",
+        ] {
+            let state = state(moves);
+            let mut tree = SearchTree::<Stats>::new(state, seeded());
+            tree.search(Budget::UntilProven, Instant::now(), None);
+            // the search stops at the first win it proves, which need not be
+            // the fastest, so only the outcome has to match
+            let root = tree.node(ROOT).score().to_value().unwrap();
+            let solved = solver.solve(&state);
+            assert_eq!(discriminant(&root), discriminant(&solved), "{}", moves);
+        }
+    }
+
+    /// AMAF statistics of every node reachable from the root by position
+    fn amaf_by_key(tree: &SearchTree<Stats>) -> HashMap<u64, String> {
+        assert_eq!(tree.amaf.len(), tree.arena.len());
+        let mut amaf = HashMap::new();
+        let mut stack = vec![(ROOT, tree.state)];
+        while let Some((id, state)) = stack.pop() {
+            let key = state.board.key();
+            if amaf
+                .insert(key, format!("{:?}", tree.amaf[id as usize]))
+                .is_none()
+            {
+                for edge in tree.arena.edges(id) {
+                    let mut s = state;
+                    s.apply_move(edge.column);
+                    stack.push((edge.child, s));
+                }
+            }
+        }
+        amaf
+    }
+
+    #[test]
+    fn rerooting_moves_the_amaf_statistics_with_their_nodes() {
+        let config = SearchConfig {
+            rave: Some(100.0),
+            ..seeded()
+        };
+        let mut tree = SearchTree::<Stats>::new(state(""), config);
+        tree.search(Budget::Iterations(2000), Instant::now(), None);
+        let before = amaf_by_key(&tree);
+
+        assert!(tree.reroot(&state("4")).is_some());
+        let after = amaf_by_key(&tree);
+        assert!(after.len() > 1);
+        for (key, amaf) in &after {
+            assert_eq!(amaf, &before[key]);
+        }
+
+        // a position off the graph becomes a new root in front of the old one
+        let old_root = before[&state("4").board.key()].clone();
+        assert!(tree.reroot(&state("1")).is_none());
+        assert_eq!(tree.amaf.len(), tree.arena.len());
+        let old = tree.arena.get(&state("4")).unwrap();
+        assert_eq!(format!("{:?}", tree.amaf[old as usize]), old_root);
+    }
+}
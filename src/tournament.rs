@@ -0,0 +1,292 @@
+use itertools::Itertools;
+
+use crate::board::Player;
+use crate::openings::Openings;
+use crate::runner::Runner;
+use crate::runner::TimeControl;
+use crate::state::GameState;
+use crate::state::State;
+use crate::strategy::Agent;
+
+/// Builds a fresh agent for every game so no search state carries over
+pub type AgentFactory = Box<dyn Fn() -> Box<dyn Agent>>;
+
+/// Wins, draws and losses of one player against one or more opponents
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// Elo difference with a 95% confidence interval
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elo {
+    pub diff: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Elo {
+    /// Elo difference corresponding to an expected score between 0 and 1
+    pub fn from_score(score: f64) -> f64 {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+
+    /// Expected score of a player with an Elo advantage of `diff`
+    pub fn expected_score(diff: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf(-diff / 400.0))
+    }
+}
+
+impl std::fmt::Display for Elo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:+.1} [{:+.1}, {:+.1}]",
+            self.diff, self.lower, self.upper
+        )
+    }
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, counting draws as half a point
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Record as seen from the opponent
+    pub fn reverse(&self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    pub fn add(&mut self, result: State, player: Player) {
+        match result {
+            State::Win(winner) if winner == player => self.wins += 1,
+            State::Win(_) => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+
+    /// Elo difference against the opponents, `None` before any games
+    pub fn elo(&self) -> Option<Elo> {
+        if self.games() == 0 {
+            return None;
+        }
+        let games = self.games() as f64;
+        let score = self.score();
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+        let margin = 1.96 * (variance / games).sqrt();
+        Some(Elo {
+            diff: Elo::from_score(score),
+            lower: Elo::from_score((score - margin).max(0.0)),
+            upper: Elo::from_score((score + margin).min(1.0)),
+        })
+    }
+}
+
+impl std::ops::AddAssign for Record {
+    fn add_assign(&mut self, other: Self) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)
+    }
+}
+
+/// Play one game from `start` and return its result
+pub fn play_game(
+    yellow: &AgentFactory,
+    red: &AgentFactory,
+    control: TimeControl,
+    start: GameState,
+) -> State {
+    let (yellow, red) = (yellow(), red());
+    Runner::new(yellow.as_ref(), red.as_ref(), control)
+        .play(start)
+        .result()
+}
+
+/// Play two games from `start` with colors swapped, `a` playing Yellow first,
+/// returning the record of `a`
+pub fn play_pair(
+    a: &AgentFactory,
+    b: &AgentFactory,
+    control: TimeControl,
+    start: GameState,
+) -> Record {
+    let mut record = Record::default();
+    record.add(play_game(a, b, control, start), Player::Yellow);
+    record.add(play_game(b, a, control, start), Player::Red);
+    record
+}
+
+/// Round-robin tournament where every pair of entrants plays a number of game
+/// pairs, each agent playing either color once per pair
+pub struct Tournament {
+    entrants: Vec<(String, AgentFactory)>,
+    pairs: usize,
+    control: TimeControl,
+    openings: Openings,
+}
+
+impl Tournament {
+    pub fn new(pairs: usize, control: TimeControl) -> Self {
+        Self {
+            entrants: vec![],
+            pairs,
+            control,
+            openings: Openings::from(GameState::default()),
+        }
+    }
+
+    /// Start the game pairs of every pairing from successive `openings`
+    pub fn with_openings(mut self, openings: Openings) -> Self {
+        self.openings = openings;
+        self
+    }
+
+    pub fn add<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Box<dyn Agent> + 'static,
+    {
+        self.entrants.push((name.into(), Box::new(factory)));
+    }
+
+    pub fn play(&self) -> Standings {
+        let n = self.entrants.len();
+        let mut records = vec![vec![Record::default(); n]; n];
+        for (i, j) in (0..n).tuple_combinations() {
+            for pair in 0..self.pairs {
+                let record = play_pair(
+                    &self.entrants[i].1,
+                    &self.entrants[j].1,
+                    self.control,
+                    self.openings.get(pair),
+                );
+                records[i][j] += record;
+                records[j][i] += record.reverse();
+            }
+        }
+        Standings {
+            names: self.entrants.iter().map(|(name, _)| name.clone()).collect(),
+            records,
+        }
+    }
+}
+
+/// Results of a tournament
+pub struct Standings {
+    pub names: Vec<String>,
+    // records[i][j] is the record of entrant i against entrant j
+    records: Vec<Vec<Record>>,
+}
+
+impl Standings {
+    pub fn record(&self, player: usize, opponent: usize) -> Record {
+        self.records[player][opponent]
+    }
+
+    /// Record of an entrant against the whole field
+    pub fn total(&self, player: usize) -> Record {
+        let mut total = Record::default();
+        self.records[player].iter().for_each(|&r| total += r);
+        total
+    }
+
+    /// Elo of an entrant relative to the average of its opponents
+    pub fn elo(&self, player: usize) -> Option<Elo> {
+        self.total(player).elo()
+    }
+}
+
+impl std::fmt::Display for Standings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let mut order = (0..self.names.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| self.total(b).score().total_cmp(&self.total(a).score()));
+        for i in order {
+            let total = self.total(i);
+            let elo = self.elo(i).map_or("-".to_string(), |elo| elo.to_string());
+            write!(
+                f,
+                "{:width$}  {:>16}  {:>6.1}%  {}",
+                self.names[i],
+                total.to_string(),
+                total.score() * 100.0,
+                elo
+            )?;
+            writeln!(f)?;
+            for (j, name) in self.names.iter().enumerate() {
+                if i != j {
+                    writeln!(
+                        f,
+                        "{:width$}    vs {:width$}  {}",
+                        "", name, self.records[i][j]
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Column;
+    use crate::game::Game;
+
+    /// Always plays the leftmost column that is not full
+    struct Leftmost;
+
+    impl Agent for Leftmost {
+        fn next_move(&self, state: &GameState) -> Column {
+            state.possible_moves()[0]
+        }
+    }
+
+    /// Takes wins, blocks losses and prefers the center otherwise
+    struct Greedy;
+
+    impl Agent for Greedy {
+        fn next_move(&self, state: &GameState) -> Column {
+            let moves = state.possible_moves();
+            let center = [Column::D, Column::C, Column::E, Column::B, Column::F];
+            let preferred = center.into_iter().find(|c| moves.contains(c));
+            state
+                .winning_moves()
+                .first()
+                .or(state.forced_moves().first())
+                .copied()
+                .or(preferred)
+                .unwrap_or(moves[0])
+        }
+    }
+
+    #[test]
+    fn play_pair_credits_colors_from_odd_openings() {
+        let start = *"445".parse::<Game>().unwrap().state();
+        assert_eq!(start.current_player, Player::Red);
+        let strong: AgentFactory = Box::new(|| Box::new(Greedy));
+        let weak: AgentFactory = Box::new(|| Box::new(Leftmost));
+        let record = play_pair(&strong, &weak, TimeControl::Unlimited, start);
+        assert_eq!(record.wins, 2, "{}", record);
+    }
+}
@@ -0,0 +1,111 @@
+/// Policy deciding whether a new entry evicts the one already stored in its slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    /// Always overwrite the existing entry
+    Always,
+    /// Only overwrite entries with a lower or equal priority
+    Priority,
+}
+
+/// Fixed size hash table of values keyed by `Board::key`, shared between searches
+pub struct TranspositionTable<V> {
+    // 0 marks an empty slot, as no board has that key
+    keys: Vec<u64>,
+    values: Vec<V>,
+    // priority of every entry, only kept with `Replacement::Priority`
+    priorities: Vec<u32>,
+    replacement: Replacement,
+}
+
+impl<V: Copy + Default> TranspositionTable<V> {
+    pub fn new(size: usize, replacement: Replacement) -> Self {
+        assert!(size > 0, "table size must be positive");
+        let priorities = match replacement {
+            Replacement::Always => vec![],
+            Replacement::Priority => vec![0; size],
+        };
+        Self {
+            keys: vec![0; size],
+            values: vec![V::default(); size],
+            priorities,
+            replacement,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as usize % self.keys.len()
+    }
+
+    pub fn get(&self, key: u64) -> Option<V> {
+        let i = self.index(key);
+        (self.keys[i] == key).then(|| self.values[i])
+    }
+
+    /// Store `value` for `key`, returning whether it was kept by the replacement policy
+    pub fn insert(&mut self, key: u64, value: V, priority: u32) -> bool {
+        let i = self.index(key);
+        let replace = match self.replacement {
+            Replacement::Always => true,
+            Replacement::Priority => {
+                self.keys[i] == 0 || self.keys[i] == key || self.priorities[i] <= priority
+            }
+        };
+        if replace {
+            self.keys[i] = key;
+            self.values[i] = value;
+            if let Some(p) = self.priorities.get_mut(i) {
+                *p = priority;
+            }
+        }
+        replace
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn always_overwrites_colliding_entries() {
+        // with a single slot every key collides
+        let mut table = TranspositionTable::new(1, Replacement::Always);
+        assert!(table.insert(1, 10u8, 5));
+        assert!(table.insert(2, 20, 0));
+        assert_eq!(table.get(1), None);
+        assert_eq!(table.get(2), Some(20));
+    }
+
+    #[test]
+    fn priority_keeps_entries_of_higher_priority() {
+        let mut table = TranspositionTable::new(1, Replacement::Priority);
+        assert!(table.insert(1, 10u8, 5));
+        assert!(!table.insert(2, 20, 4));
+        assert_eq!(table.get(1), Some(10));
+        assert_eq!(table.get(2), None);
+        // equal priorities replace, as do updates of the same key
+        assert!(table.insert(2, 20, 5));
+        assert!(table.insert(2, 21, 0));
+        assert_eq!(table.get(2), Some(21));
+        assert!(table.insert(1, 10, 0));
+    }
+
+    #[test]
+    fn clear_empties_every_slot() {
+        let mut table = TranspositionTable::new(64, Replacement::Priority);
+        for key in 1..=16 {
+            table.insert(key, key as u8, 100);
+        }
+        table.clear();
+        assert!((1..=16).all(|key| table.get(key).is_none()));
+        // cleared slots take entries of any priority
+        assert!(table.insert(3, 3, 0));
+    }
+}